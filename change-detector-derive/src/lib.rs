@@ -0,0 +1,156 @@
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::ext::IdentExt;
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Error, Expr, Fields, Ident, Type};
+
+/// How a field is compared, set with `#[change(...)]`
+enum Mode {
+    Hash,
+    Tolerance(Expr),
+}
+
+struct TrackedField {
+    /// Field name as reported to the user, the index for tuple structs
+    name: String,
+    /// Member used to access the field on the value
+    member: syn::Member,
+    /// Field of the generated tracker, prefixed so it can't clash with `__change_observed` and `__change_phantom`
+    ident: Ident,
+    ty: Type,
+    mode: Mode,
+    key: bool,
+    ignore: bool,
+}
+
+/// Implements `change_detector::ChangeTrack`, generating a `<Name>Tracker` that reports which fields changed.
+///
+/// Fields accept `#[change(ignore)]`, `#[change(key)]` and `#[change(tolerance = <threshold>)]`.
+#[proc_macro_derive(ChangeTrack, attributes(change))]
+pub fn derive_change_track(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => return Err(Error::new(Span::call_site(), "ChangeTrack can only be derived for structs")),
+    };
+    let mut tracked = parse_fields(fields)?;
+    if tracked.iter().any(|field| field.key) {
+        tracked.retain(|field| field.key);
+    }
+    tracked.retain(|field| !field.ignore);
+
+    let name = &input.ident;
+    let vis = &input.vis;
+    let tracker = format_ident!("{}Tracker", name);
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let mut where_clause = where_clause.cloned().unwrap_or_else(|| parse_quote!(where));
+
+    let mut tracker_fields = Vec::new();
+    let mut constructors = Vec::new();
+    let mut detections = Vec::new();
+    for field in &tracked {
+        let TrackedField { name, member, ident, ty, .. } = field;
+        match &field.mode {
+            Mode::Hash => {
+                where_clause.predicates.push(parse_quote!(#ty : ::std::hash::Hash));
+                tracker_fields.push(quote!(#ident: ::change_detector::ChangeDetector<#ty>));
+                constructors.push(quote!(#ident: ::change_detector::ChangeDetector::new()));
+            }
+            Mode::Tolerance(threshold) => {
+                where_clause.predicates.push(parse_quote!(#ty : ::change_detector::Tolerance + ::std::clone::Clone));
+                tracker_fields.push(quote!(#ident: ::change_detector::NumericChangeDetector<#ty>));
+                constructors.push(quote!(#ident: ::change_detector::NumericChangeDetector::new(#threshold)));
+            }
+        }
+        detections.push(quote! {
+            if self.#ident.detect(&value.#member).is_some() {
+                changed.push(#name);
+            }
+        });
+    }
+
+    let doc = format!("Reports which fields of [`{name}`] changed, generated by `#[derive(ChangeTrack)]`");
+    Ok(quote! {
+        #[doc = #doc]
+        #vis struct #tracker #impl_generics #where_clause {
+            __change_observed: bool,
+            #(#tracker_fields,)*
+            __change_phantom: ::std::marker::PhantomData<fn(&#name #ty_generics)>,
+        }
+
+        impl #impl_generics ::change_detector::FieldTracker<#name #ty_generics> for #tracker #ty_generics #where_clause {
+            fn detect(&mut self, value: &#name #ty_generics) -> ::std::option::Option<::std::vec::Vec<&'static str>> {
+                let mut changed = ::std::vec::Vec::new();
+                #(#detections)*
+                let first = !::std::mem::replace(&mut self.__change_observed, true);
+                if changed.is_empty() && !first {
+                    ::std::option::Option::None
+                }
+                else {
+                    ::std::option::Option::Some(changed)
+                }
+            }
+
+            fn untouched(&self) -> bool {
+                !self.__change_observed
+            }
+        }
+
+        impl #impl_generics ::change_detector::ChangeTrack for #name #ty_generics #where_clause {
+            type Tracker = #tracker #ty_generics;
+
+            fn tracker() -> Self::Tracker {
+                #tracker {
+                    __change_observed: false,
+                    #(#constructors,)*
+                    __change_phantom: ::std::marker::PhantomData,
+                }
+            }
+        }
+    })
+}
+
+fn parse_fields(fields: &Fields) -> syn::Result<Vec<TrackedField>> {
+    let mut tracked = Vec::new();
+    for (index, field) in fields.iter().enumerate() {
+        let (name, member, ident) = match &field.ident {
+            Some(ident) => (ident.unraw().to_string(), syn::Member::Named(ident.clone()), format_ident!("__change_field_{}", ident.unraw())),
+            None => (index.to_string(), syn::Member::Unnamed(index.into()), format_ident!("__change_field_{}", index)),
+        };
+        let mut tracked_field = TrackedField {
+            name,
+            member,
+            ident,
+            ty: field.ty.clone(),
+            mode: Mode::Hash,
+            key: false,
+            ignore: false,
+        };
+        for attribute in field.attrs.iter().filter(|attribute| attribute.path().is_ident("change")) {
+            attribute.parse_nested_meta(|meta| {
+                if meta.path.is_ident("ignore") {
+                    tracked_field.ignore = true;
+                }
+                else if meta.path.is_ident("key") {
+                    tracked_field.key = true;
+                }
+                else if meta.path.is_ident("tolerance") {
+                    tracked_field.mode = Mode::Tolerance(meta.value()?.parse()?);
+                }
+                else {
+                    return Err(meta.error("expected `ignore`, `key` or `tolerance = ...`"));
+                }
+                Ok(())
+            })?;
+        }
+        if tracked_field.ignore && tracked_field.key {
+            return Err(Error::new_spanned(field, "a field can't be both `ignore` and `key`"));
+        }
+        tracked.push(tracked_field);
+    }
+    Ok(tracked)
+}
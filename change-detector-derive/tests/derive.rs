@@ -0,0 +1,68 @@
+use change_detector::{ChangeTrack, FieldTracker, Threshold};
+
+#[derive(Debug, Clone, ChangeTrack)]
+struct Reading {
+    sensor: String,
+    #[change(tolerance = Threshold::Absolute(0.5))]
+    temperature: f64,
+    #[change(ignore)]
+    #[allow(dead_code)]
+    received_at: u64,
+}
+
+#[derive(ChangeTrack)]
+struct Document {
+    #[change(key)]
+    id: u32,
+    #[change(key)]
+    version: u32,
+    #[allow(dead_code)]
+    body: String,
+}
+
+#[derive(ChangeTrack)]
+struct Sensor {
+    observed: bool,
+    phantom: u32,
+}
+
+#[derive(ChangeTrack)]
+struct Pair<T>(T, #[change(ignore)] T);
+
+#[test]
+fn reports_changed_fields() {
+    let mut tracker = Reading::tracker();
+    assert!(tracker.untouched());
+    let reading = Reading { sensor: "kitchen".to_string(), temperature: 20.0, received_at: 1 };
+    assert_eq!(tracker.detect(&reading), Some(vec!["sensor", "temperature"]));
+    assert!(!tracker.untouched());
+
+    let later = Reading { temperature: 20.3, received_at: 2, ..reading.clone() };
+    assert_eq!(tracker.detect(&later), None);
+
+    let warmer = Reading { temperature: 21.0, received_at: 3, ..reading };
+    assert_eq!(tracker.detect(&warmer), Some(vec!["temperature"]));
+}
+
+#[test]
+fn only_key_fields_are_tracked() {
+    let mut tracker = Document::tracker();
+    assert_eq!(tracker.detect(&Document { id: 1, version: 1, body: "A".to_string() }), Some(vec!["id", "version"]));
+    assert_eq!(tracker.detect(&Document { id: 1, version: 1, body: "B".to_string() }), None);
+    assert_eq!(tracker.detect(&Document { id: 1, version: 2, body: "B".to_string() }), Some(vec!["version"]));
+}
+
+#[test]
+fn tuple_and_generic_structs_work() {
+    let mut tracker = Pair::<&str>::tracker();
+    assert_eq!(tracker.detect(&Pair("a", "b")), Some(vec!["0"]));
+    assert_eq!(tracker.detect(&Pair("a", "c")), None);
+    assert_eq!(tracker.detect(&Pair("b", "c")), Some(vec!["0"]));
+}
+
+#[test]
+fn field_names_dont_clash_with_the_tracker() {
+    let mut tracker = Sensor::tracker();
+    assert_eq!(tracker.detect(&Sensor { observed: false, phantom: 1 }), Some(vec!["observed", "phantom"]));
+    assert_eq!(tracker.detect(&Sensor { observed: true, phantom: 1 }), Some(vec!["observed"]));
+}
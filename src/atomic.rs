@@ -0,0 +1,213 @@
+use std::borrow::Borrow;
+use std::hash::Hash;
+use std::marker::PhantomData;
+#[cfg(loom)]
+use loom::sync::atomic::{AtomicU64, Ordering};
+#[cfg(not(loom))]
+use std::sync::atomic::{AtomicU64, Ordering};
+use crate::{DefaultState, HashBackend};
+
+/// Stored before the first value
+const UNTOUCHED: u64 = 0;
+/// Set on every stored hash so it can't be mistaken for [`UNTOUCHED`]
+const OBSERVED: u64 = 1 << 63;
+
+/// Lock-free [`crate::ChangeDetector`] that can be shared between threads, e.g. in an `Arc`.
+///
+/// The hash is swapped in with a compare-and-swap, so among concurrent callers submitting
+/// the same new value exactly one observes the change.
+///
+/// The top bit of the atomic marks that a value has been observed, so only the lower 63 bits of the digest are compared.
+/// Values whose digests only differ in the top bit are treated as equal.
+///
+/// # Memory ordering
+///
+/// The swap uses `AcqRel` and loads use `Acquire`. A thread that gets `None` because another
+/// thread already stored the hash therefore also sees every write that thread made before its `detect` call.
+pub struct AtomicChangeDetector<T, S = DefaultState> where T : ?Sized {
+    hash: AtomicU64,
+    backend: S,
+    phantom: PhantomData<fn(&T)>,
+}
+
+impl <T> AtomicChangeDetector<T> where T : ?Sized {
+    pub fn new() -> AtomicChangeDetector<T> {
+        AtomicChangeDetector::with_hasher(DefaultState::default())
+    }
+}
+
+impl <T> Default for AtomicChangeDetector<T> where T : ?Sized {
+    fn default() -> Self {
+        AtomicChangeDetector::new()
+    }
+}
+
+impl <T, S> AtomicChangeDetector<T, S> where T : ?Sized, S : HashBackend<Digest = u64> {
+    pub fn with_hasher(backend: S) -> AtomicChangeDetector<T, S> {
+        AtomicChangeDetector {
+            hash: AtomicU64::new(UNTOUCHED),
+            backend,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Check if detector has been used, tracked with the top bit that is taken from the hash
+    pub fn untouched(&self) -> bool {
+        self.hash.load(Ordering::Acquire) == UNTOUCHED
+    }
+
+    /// Access the lower 63 bits of the inner hash, None when no value has been observed yet
+    pub fn hash(&self) -> Option<u64> {
+        Some(self.hash.load(Ordering::Acquire)).filter(|hash| *hash != UNTOUCHED).map(|hash| hash & !OBSERVED)
+    }
+
+    /// Stores the hash and returns whether this call changed it
+    fn update(&self, hash: u64) -> bool {
+        let hash = hash | OBSERVED;
+        let mut current = self.hash.load(Ordering::Acquire);
+        loop {
+            if current == hash {
+                return false;
+            }
+            match self.hash.compare_exchange_weak(current, hash, Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) => return true,
+                Err(actual) => current = actual,
+            }
+        }
+    }
+
+    /// Returns Some when the value differs or is the first value, only for the caller that stored it
+    pub fn detect<'a, Q>(&self, value: &'a Q) -> Option<&'a Q> where T : Borrow<Q>, Q : ?Sized + Hash {
+        if self.update(self.backend.digest(value)) {
+            Some(value)
+        }
+        else {
+            None
+        }
+    }
+
+    /// Useful to avoid cloning with non-copy types like String
+    pub fn detect_owned(&self, value: T) -> Option<T> where T : Sized + Hash {
+        if self.update(self.backend.digest(&value)) {
+            Some(value)
+        }
+        else {
+            None
+        }
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use std::rc::Rc;
+    use std::sync::{Arc, Barrier};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::thread;
+    use crate::AtomicChangeDetector;
+    use crate::test_hasher::IdentityState;
+
+    #[test]
+    fn atomic_change_detect_works() {
+        let change_detector = AtomicChangeDetector::<String>::new();
+        assert!(change_detector.untouched());
+        assert_eq!(change_detector.detect("A"), Some("A"));
+        assert_eq!(change_detector.detect_owned("A".to_string()), None);
+        assert_eq!(change_detector.detect("B"), Some("B"));
+        assert!(change_detector.hash().is_some());
+    }
+
+    #[test]
+    fn only_the_top_bit_is_reserved() {
+        let change_detector = AtomicChangeDetector::<u64, _>::with_hasher(IdentityState::default());
+        assert_eq!(change_detector.detect_owned(0), Some(0));
+        assert!(!change_detector.untouched());
+        assert_eq!(change_detector.hash(), Some(0));
+        assert_eq!(change_detector.detect_owned(1), Some(1));
+        assert_eq!(change_detector.detect_owned(1 | 1 << 63), None);
+        assert_eq!(change_detector.hash(), Some(1));
+    }
+
+    #[test]
+    fn is_sync_for_any_value() {
+        fn assert_sync<T : Sync>() {}
+        assert_sync::<AtomicChangeDetector<Rc<usize>>>();
+    }
+
+    #[test]
+    fn exactly_one_thread_observes_change() {
+        for _ in 0..100 {
+            let change_detector = Arc::new(AtomicChangeDetector::<usize>::new());
+            let barrier = Arc::new(Barrier::new(8));
+            let observed = Arc::new(AtomicUsize::new(0));
+            let threads: Vec<_> = (0..8).map(|_| {
+                let change_detector = change_detector.clone();
+                let barrier = barrier.clone();
+                let observed = observed.clone();
+                thread::spawn(move || {
+                    barrier.wait();
+                    if change_detector.detect(&42).is_some() {
+                        observed.fetch_add(1, Ordering::Relaxed);
+                    }
+                })
+            }).collect();
+            for thread in threads {
+                thread.join().unwrap();
+            }
+            assert_eq!(observed.load(Ordering::Relaxed), 1);
+        }
+    }
+}
+
+/// Run with `RUSTFLAGS="--cfg loom" cargo test --release --lib atomic`
+#[cfg(all(test, loom))]
+mod loom_tests {
+    use loom::sync::Arc;
+    use loom::sync::atomic::{AtomicUsize, Ordering};
+    use loom::thread;
+    use crate::AtomicChangeDetector;
+
+    #[test]
+    fn exactly_one_thread_observes_change() {
+        loom::model(|| {
+            let change_detector = Arc::new(AtomicChangeDetector::<usize>::new());
+            let observed = Arc::new(AtomicUsize::new(0));
+            let threads: Vec<_> = (0..2).map(|_| {
+                let change_detector = change_detector.clone();
+                let observed = observed.clone();
+                thread::spawn(move || {
+                    if change_detector.detect(&42).is_some() {
+                        observed.fetch_add(1, Ordering::Relaxed);
+                    }
+                })
+            }).collect();
+            if change_detector.detect(&42).is_some() {
+                observed.fetch_add(1, Ordering::Relaxed);
+            }
+            for thread in threads {
+                thread.join().unwrap();
+            }
+            assert_eq!(observed.load(Ordering::Relaxed), 1);
+        });
+    }
+
+    #[test]
+    fn change_publishes_prior_writes() {
+        loom::model(|| {
+            let change_detector = Arc::new(AtomicChangeDetector::<usize>::new());
+            let data = Arc::new(AtomicUsize::new(0));
+            let writer = {
+                let change_detector = change_detector.clone();
+                let data = data.clone();
+                thread::spawn(move || {
+                    data.store(1, Ordering::Relaxed);
+                    change_detector.detect(&42);
+                })
+            };
+            if change_detector.detect(&42).is_none() {
+                // The writer stored the hash first, so its write must be visible
+                assert_eq!(data.load(Ordering::Relaxed), 1);
+            }
+            writer.join().unwrap();
+        });
+    }
+}
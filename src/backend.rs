@@ -0,0 +1,174 @@
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hash};
+
+/// Turns values into the digest stored by [`crate::ChangeDetector`].
+///
+/// Every `BuildHasher` is a backend with a `u64` digest, wider digests come from
+/// [`crate::Stable128State`] and the `sha256`/`blake3` features.
+pub trait HashBackend {
+    type Digest: Copy + Ord + Hash + Debug;
+
+    fn digest<V>(&self, value: &V) -> Self::Digest where V : Hash + ?Sized;
+}
+
+impl <S> HashBackend for S where S : BuildHasher {
+    type Digest = u64;
+
+    fn digest<V>(&self, value: &V) -> u64 where V : Hash + ?Sized {
+        self.hash_one(value)
+    }
+}
+
+#[cfg(feature = "sha256")]
+pub use self::sha256::Sha256State;
+
+#[cfg(feature = "sha256")]
+mod sha256 {
+    use std::hash::{Hash, Hasher};
+    use sha2::{Digest, Sha256};
+    use crate::{HashBackend, StableAlgorithm};
+    use crate::stable::stable_integer_writes;
+
+    struct Sha256Hasher(Sha256);
+
+    impl Hasher for Sha256Hasher {
+        /// Not used, the full digest is taken from the inner state
+        fn finish(&self) -> u64 {
+            let digest: [u8; 32] = self.0.clone().finalize().into();
+            u64::from_le_bytes(digest[..8].try_into().unwrap())
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            self.0.update(bytes);
+        }
+
+        stable_integer_writes! {
+            write_u16: u16 => u16,
+            write_u32: u32 => u32,
+            write_u64: u64 => u64,
+            write_u128: u128 => u128,
+            write_usize: usize => u64,
+            write_i16: i16 => i16,
+            write_i32: i32 => i32,
+            write_i64: i64 => i64,
+            write_i128: i128 => i128,
+            write_isize: isize => i64,
+        }
+    }
+
+    /// Hashes with SHA-256 over little-endian input, for when collisions must be infeasible.
+    /// Integer slices need [`crate::Portable`] like with [`crate::StableState`].
+    #[derive(Debug, Clone, Copy, Default)]
+    pub struct Sha256State;
+
+    impl HashBackend for Sha256State {
+        type Digest = [u8; 32];
+
+        fn digest<V>(&self, value: &V) -> [u8; 32] where V : Hash + ?Sized {
+            let mut hasher = Sha256Hasher(Sha256::new());
+            value.hash(&mut hasher);
+            hasher.0.finalize().into()
+        }
+    }
+
+    impl StableAlgorithm for Sha256State {
+        const ALGORITHM: &'static str = "sha256-le";
+        const VERSION: u32 = 1;
+    }
+}
+
+#[cfg(feature = "blake3")]
+pub use self::blake3::Blake3State;
+
+#[cfg(feature = "blake3")]
+mod blake3 {
+    use std::hash::{Hash, Hasher};
+    use crate::{HashBackend, StableAlgorithm};
+    use crate::stable::stable_integer_writes;
+
+    struct Blake3Hasher(::blake3::Hasher);
+
+    impl Hasher for Blake3Hasher {
+        /// Not used, the full digest is taken from the inner state
+        fn finish(&self) -> u64 {
+            let digest = self.0.finalize();
+            u64::from_le_bytes(digest.as_bytes()[..8].try_into().unwrap())
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            self.0.update(bytes);
+        }
+
+        stable_integer_writes! {
+            write_u16: u16 => u16,
+            write_u32: u32 => u32,
+            write_u64: u64 => u64,
+            write_u128: u128 => u128,
+            write_usize: usize => u64,
+            write_i16: i16 => i16,
+            write_i32: i32 => i32,
+            write_i64: i64 => i64,
+            write_i128: i128 => i128,
+            write_isize: isize => i64,
+        }
+    }
+
+    /// Hashes with BLAKE3 over little-endian input, a faster alternative to SHA-256.
+    /// Integer slices need [`crate::Portable`] like with [`crate::StableState`].
+    #[derive(Debug, Clone, Copy, Default)]
+    pub struct Blake3State;
+
+    impl HashBackend for Blake3State {
+        type Digest = [u8; 32];
+
+        fn digest<V>(&self, value: &V) -> [u8; 32] where V : Hash + ?Sized {
+            let mut hasher = Blake3Hasher(::blake3::Hasher::new());
+            value.hash(&mut hasher);
+            *hasher.0.finalize().as_bytes()
+        }
+    }
+
+    impl StableAlgorithm for Blake3State {
+        const ALGORITHM: &'static str = "blake3-le";
+        const VERSION: u32 = 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ChangeDetector, HashBackend, Stable128State, StableState};
+
+    #[test]
+    fn digest_width_follows_backend() {
+        let mut change_detector = ChangeDetector::<&str, _>::with_hasher(Stable128State);
+        assert_eq!(change_detector.detect(&"A"), Some(&"A"));
+        assert_eq!(change_detector.detect(&"A"), None);
+        let hash: Option<u128> = change_detector.hash();
+        assert_eq!(hash, Some(Stable128State.digest("A")));
+        assert_ne!(hash.map(|hash| hash as u64), Some(StableState.digest("A")));
+    }
+
+    #[cfg(feature = "sha256")]
+    #[test]
+    fn sha256_works() {
+        use crate::Sha256State;
+        let mut change_detector = ChangeDetector::<Vec<u8>, _>::with_hasher(Sha256State);
+        assert_eq!(change_detector.detect_owned(vec![1, 2]), Some(vec![1, 2]));
+        assert_eq!(change_detector.detect_owned(vec![1, 2]), None);
+        assert_eq!(change_detector.detect_owned(vec![2, 1]), Some(vec![2, 1]));
+        // SHA-256 of the length prefix as 8 little-endian bytes followed by the elements,
+        // which are portable only because they are single bytes, wider integers need `Portable`
+        assert_eq!(change_detector.hash().map(|hash| hash[..4].to_vec()), Some(vec![0x80, 0x95, 0x6c, 0xf3]));
+    }
+
+    #[cfg(feature = "blake3")]
+    #[test]
+    fn blake3_works() {
+        use crate::Blake3State;
+        let mut change_detector = ChangeDetector::<Vec<u8>, _>::with_hasher(Blake3State);
+        assert_eq!(change_detector.detect_owned(vec![1, 2]), Some(vec![1, 2]));
+        assert_eq!(change_detector.detect_owned(vec![1, 2]), None);
+        let hash: Option<[u8; 32]> = change_detector.hash();
+        assert_eq!(hash, Some(*blake3::hash(&[2, 0, 0, 0, 0, 0, 0, 0, 1, 2]).as_bytes()));
+    }
+}
@@ -0,0 +1,77 @@
+/// Outcome of [`crate::ChangeDetector::detect_change`], distinguishing the first value from a real change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Change<V, D = u64> {
+    /// No value had been observed before
+    First(V),
+    /// The value differs from the previously observed one
+    Changed { value: V, previous_hash: D },
+    /// The value matches the previously observed one
+    Unchanged,
+}
+
+impl <V, D> Change<V, D> {
+    /// Returns Some when the value differs or is the first value, like [`crate::ChangeDetector::detect`]
+    pub fn into_option(self) -> Option<V> {
+        match self {
+            Change::First(value) | Change::Changed { value, .. } => Some(value),
+            Change::Unchanged => None,
+        }
+    }
+
+    /// Returns Some only for a change from a known previous value, skipping the first one
+    pub fn changed(self) -> Option<V> {
+        match self {
+            Change::Changed { value, .. } => Some(value),
+            Change::First(_) | Change::Unchanged => None,
+        }
+    }
+
+    pub fn is_first(&self) -> bool {
+        matches!(self, Change::First(_))
+    }
+
+    pub fn is_changed(&self) -> bool {
+        matches!(self, Change::Changed { .. })
+    }
+
+    pub fn is_unchanged(&self) -> bool {
+        matches!(self, Change::Unchanged)
+    }
+}
+
+impl <V, D> From<Change<V, D>> for Option<V> {
+    fn from(change: Change<V, D>) -> Self {
+        change.into_option()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Change, ChangeDetector};
+
+    #[test]
+    fn detect_change_works() {
+        let mut change_detector = ChangeDetector::<usize>::new();
+        assert_eq!(change_detector.detect_change(&1), Change::First(&1));
+        let first_hash = change_detector.hash().unwrap();
+        assert_eq!(change_detector.detect_change(&1), Change::Unchanged);
+        assert_eq!(change_detector.detect_change_owned(2), Change::Changed { value: 2, previous_hash: first_hash });
+    }
+
+    #[test]
+    fn change_helpers_work() {
+        let first: Change<usize> = Change::First(1);
+        assert!(first.is_first());
+        assert_eq!(first.into_option(), Some(1));
+        assert_eq!(first.changed(), None);
+
+        let changed: Change<usize> = Change::Changed { value: 2, previous_hash: 0 };
+        assert!(changed.is_changed());
+        assert_eq!(Option::from(changed), Some(2));
+        assert_eq!(changed.changed(), Some(2));
+
+        let unchanged: Change<usize> = Change::Unchanged;
+        assert!(unchanged.is_unchanged());
+        assert_eq!(unchanged.into_option(), None);
+    }
+}
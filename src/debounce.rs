@@ -0,0 +1,158 @@
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+use crate::{ChangeDetector, Clock, DefaultState, HashBackend, SystemClock};
+
+/// Only reports a value once it has stayed the same for a duration, to ignore values that flap.
+///
+/// The value waiting to become stable is kept, it is reported by the next call after the delay,
+/// call [`DebouncedDetector::poll`] to pick it up when no new values arrive.
+/// The first value is debounced as well, and flapping back to the last reported value cancels the pending change.
+pub struct DebouncedDetector<T, C = SystemClock, S = DefaultState> where S : HashBackend {
+    detector: ChangeDetector<T, S>,
+    clock: C,
+    delay: Duration,
+    pending: Option<(S::Digest, Instant, T)>,
+}
+
+impl <T> DebouncedDetector<T> {
+    pub fn new(delay: Duration) -> DebouncedDetector<T> {
+        DebouncedDetector::with_clock(delay, SystemClock)
+    }
+}
+
+impl <T, C> DebouncedDetector<T, C> where C : Clock {
+    pub fn with_clock(delay: Duration, clock: C) -> DebouncedDetector<T, C> {
+        DebouncedDetector::with_clock_and_hasher(delay, clock, DefaultState::default())
+    }
+}
+
+impl <T, C, S> DebouncedDetector<T, C, S> where C : Clock, S : HashBackend {
+    pub fn with_clock_and_hasher(delay: Duration, clock: C, backend: S) -> DebouncedDetector<T, C, S> {
+        DebouncedDetector {
+            detector: ChangeDetector::with_hasher(backend),
+            clock,
+            delay,
+            pending: None,
+        }
+    }
+
+    /// Check if a value has been reported
+    pub fn untouched(&self) -> bool {
+        self.detector.untouched()
+    }
+
+    /// Access the detector, which holds the hash of the last reported value
+    pub fn detector(&self) -> &ChangeDetector<T, S> {
+        &self.detector
+    }
+
+    /// Access the value that is waiting to become stable
+    pub fn pending(&self) -> Option<&T> {
+        self.pending.as_ref().map(|(_, _, value)| value)
+    }
+
+    /// When the waiting value can be reported, None when nothing is waiting
+    pub fn deadline(&self) -> Option<Instant> {
+        self.pending.as_ref().map(|(_, since, _)| *since + self.delay)
+    }
+}
+
+impl <T, C, S> DebouncedDetector<T, C, S> where T : Hash, C : Clock, S : HashBackend {
+    /// Returns Some when the value differs from the last reported value and has been the same for the delay,
+    /// otherwise a change is kept until it is stable
+    pub fn detect_owned(&mut self, value: T) -> Option<T> {
+        let hash = self.detector.backend.digest(&value);
+        if self.detector.hash == Some(hash) {
+            self.pending = None;
+            return None;
+        }
+        let now = self.clock.now();
+        let since = match self.pending {
+            Some((pending, since, _)) if pending == hash => since,
+            _ => now,
+        };
+        if now.duration_since(since) >= self.delay {
+            self.pending = None;
+            self.detector.update(hash);
+            Some(value)
+        }
+        else {
+            self.pending = Some((hash, since, value));
+            None
+        }
+    }
+
+    /// Returns the waiting value once it has been stable for the delay
+    pub fn poll(&mut self) -> Option<T> {
+        let now = self.clock.now();
+        if self.deadline().is_none_or(|deadline| now < deadline) {
+            return None;
+        }
+        let (hash, _, value) = self.pending.take()?;
+        self.detector.update(hash);
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::{Clock, DebouncedDetector, ManualClock};
+
+    #[test]
+    fn debounce_works() {
+        let clock = ManualClock::new();
+        let mut change_detector = DebouncedDetector::with_clock(Duration::from_secs(5), clock.clone());
+        assert_eq!(change_detector.detect_owned("A"), None);
+        assert_eq!(change_detector.pending(), Some(&"A"));
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(change_detector.detect_owned("A"), Some("A"));
+        assert_eq!(change_detector.pending(), None);
+
+        // Flapping restarts the delay
+        assert_eq!(change_detector.detect_owned("B"), None);
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(change_detector.detect_owned("C"), None);
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(change_detector.detect_owned("C"), None);
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(change_detector.detect_owned("C"), Some("C"));
+    }
+
+    #[test]
+    fn flapping_back_cancels() {
+        let clock = ManualClock::new();
+        let mut change_detector = DebouncedDetector::<u32, _>::with_clock(Duration::from_secs(1), clock.clone());
+        assert_eq!(change_detector.detect_owned(1), None);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(change_detector.detect_owned(1), Some(1));
+
+        assert_eq!(change_detector.detect_owned(2), None);
+        assert_eq!(change_detector.detect_owned(1), None);
+        assert_eq!(change_detector.pending(), None);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(change_detector.detect_owned(1), None);
+        assert_eq!(change_detector.poll(), None);
+    }
+
+    #[test]
+    fn settled_value_is_polled() {
+        let clock = ManualClock::new();
+        let mut change_detector = DebouncedDetector::with_clock(Duration::from_secs(5), clock.clone());
+        assert_eq!(change_detector.detect_owned("A"), None);
+        clock.advance(Duration::from_secs(5));
+        assert_eq!(change_detector.poll(), Some("A"));
+
+        // The rollout flaps and then goes quiet
+        for value in ["B", "A", "B"] {
+            assert_eq!(change_detector.detect_owned(value), None);
+        }
+        clock.advance(Duration::from_secs(4));
+        assert_eq!(change_detector.poll(), None);
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(change_detector.deadline(), Some(clock.now()));
+        assert_eq!(change_detector.poll(), Some("B"));
+        assert_eq!(change_detector.poll(), None);
+        assert_eq!(change_detector.detect_owned("B"), None);
+    }
+}
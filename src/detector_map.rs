@@ -0,0 +1,149 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+use crate::{ChangeDetector, DefaultState, HashBackend};
+
+struct Entry<V, S> where S : HashBackend {
+    detector: ChangeDetector<V, S>,
+    /// Sweep in which the key was last observed
+    sweep: u64,
+}
+
+/// A [`ChangeDetector`] per key, e.g. per user, which also finds keys that are no longer observed.
+///
+/// Call [`ChangeDetectorMap::begin_sweep`] before observing all entities, afterwards
+/// [`ChangeDetectorMap::unobserved`] returns the keys that weren't passed to `detect` since.
+pub struct ChangeDetectorMap<K, V, S = DefaultState> where S : HashBackend {
+    entries: HashMap<K, Entry<V, S>>,
+    backend: S,
+    sweep: u64,
+}
+
+impl <K, V> ChangeDetectorMap<K, V> where K : Eq + Hash, V : Hash {
+    pub fn new() -> ChangeDetectorMap<K, V> {
+        ChangeDetectorMap::with_hasher(DefaultState::default())
+    }
+}
+
+impl <K, V> Default for ChangeDetectorMap<K, V> where K : Eq + Hash, V : Hash {
+    fn default() -> Self {
+        ChangeDetectorMap::new()
+    }
+}
+
+impl <K, V, S> ChangeDetectorMap<K, V, S> where K : Eq + Hash, V : Hash, S : HashBackend + Clone {
+    /// Every detector in the map uses a clone of `backend`
+    pub fn with_hasher(backend: S) -> ChangeDetectorMap<K, V, S> {
+        ChangeDetectorMap {
+            entries: HashMap::new(),
+            backend,
+            sweep: 0,
+        }
+    }
+
+    /// Number of tracked keys
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool where K : Borrow<Q>, Q : ?Sized + Eq + Hash {
+        self.entries.contains_key(key)
+    }
+
+    /// Access the detector of a key
+    pub fn get<Q>(&self, key: &Q) -> Option<&ChangeDetector<V, S>> where K : Borrow<Q>, Q : ?Sized + Eq + Hash {
+        self.entries.get(key).map(|entry| &entry.detector)
+    }
+
+    /// Returns Some when the value of the key differs or is the first value, and marks the key as observed
+    pub fn detect<'a>(&mut self, key: K, value: &'a V) -> Option<&'a V> {
+        self.observe(key).detect(value)
+    }
+
+    /// Useful to avoid cloning with non-copy types like String
+    pub fn detect_owned(&mut self, key: K, value: V) -> Option<V> {
+        self.observe(key).detect_owned(value)
+    }
+
+    fn observe(&mut self, key: K) -> &mut ChangeDetector<V, S> {
+        let entry = self.entries.entry(key).or_insert_with(|| Entry {
+            detector: ChangeDetector::with_hasher(self.backend.clone()),
+            sweep: self.sweep,
+        });
+        entry.sweep = self.sweep;
+        &mut entry.detector
+    }
+
+    /// Stops tracking a key, returns whether it was tracked
+    pub fn remove<Q>(&mut self, key: &Q) -> bool where K : Borrow<Q>, Q : ?Sized + Eq + Hash {
+        self.entries.remove(key).is_some()
+    }
+
+    /// Only keeps tracking the keys for which `f` returns true
+    pub fn retain<F>(&mut self, mut f: F) where F : FnMut(&K) -> bool {
+        self.entries.retain(|key, _| f(key));
+    }
+
+    /// Starts a new sweep, after which every key counts as unobserved until it is passed to `detect` again
+    pub fn begin_sweep(&mut self) {
+        self.sweep += 1;
+    }
+
+    /// Keys that haven't been observed since the latest [`ChangeDetectorMap::begin_sweep`]
+    pub fn unobserved(&self) -> impl Iterator<Item = &K> {
+        self.entries.iter()
+            .filter(|(_, entry)| entry.sweep != self.sweep)
+            .map(|(key, _)| key)
+    }
+
+    /// Stops tracking the unobserved keys and returns them, e.g. to report deleted entities
+    pub fn remove_unobserved(&mut self) -> Vec<K> {
+        let sweep = self.sweep;
+        let unobserved = self.entries.extract_if(|_, entry| entry.sweep != sweep);
+        unobserved.map(|(key, _)| key).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ChangeDetectorMap;
+
+    #[test]
+    fn detects_per_key() {
+        let mut change_detectors = ChangeDetectorMap::<&str, usize>::new();
+        assert_eq!(change_detectors.detect("a", &1), Some(&1));
+        assert_eq!(change_detectors.detect("b", &1), Some(&1));
+        assert_eq!(change_detectors.detect("a", &1), None);
+        assert_eq!(change_detectors.detect_owned("b", 2), Some(2));
+        assert_eq!(change_detectors.len(), 2);
+
+        assert!(change_detectors.remove("a"));
+        assert!(!change_detectors.contains_key("a"));
+        assert_eq!(change_detectors.detect("a", &1), Some(&1));
+
+        change_detectors.retain(|key| *key == "b");
+        assert_eq!(change_detectors.len(), 1);
+        assert!(change_detectors.get("b").and_then(|detector| detector.hash()).is_some());
+    }
+
+    #[test]
+    fn finds_unobserved_keys() {
+        let mut change_detectors = ChangeDetectorMap::<String, usize>::new();
+        for (user, setting) in [("alice", 1), ("bob", 2), ("carol", 3)] {
+            change_detectors.detect_owned(user.to_string(), setting);
+        }
+        assert_eq!(change_detectors.unobserved().count(), 0);
+
+        change_detectors.begin_sweep();
+        assert_eq!(change_detectors.detect_owned("alice".to_string(), 1), None);
+        assert_eq!(change_detectors.detect_owned("carol".to_string(), 4), Some(4));
+        assert_eq!(change_detectors.unobserved().collect::<Vec<_>>(), vec!["bob"]);
+        assert_eq!(change_detectors.remove_unobserved(), vec!["bob".to_string()]);
+        assert_eq!(change_detectors.len(), 2);
+        assert!(change_detectors.remove_unobserved().is_empty());
+    }
+}
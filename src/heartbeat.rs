@@ -0,0 +1,171 @@
+use std::borrow::Borrow;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+use crate::{ChangeDetector, Clock, DefaultState, HashBackend, SystemClock};
+
+/// When to report an unchanged value again, both limits can be combined and whichever is reached first applies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Heartbeat {
+    /// Maximum time since the last reported value
+    pub interval: Option<Duration>,
+    /// Maximum number of unchanged values since the last reported value
+    pub calls: Option<usize>,
+}
+
+impl Heartbeat {
+    pub fn every(interval: Duration) -> Heartbeat {
+        Heartbeat {
+            interval: Some(interval),
+            calls: None,
+        }
+    }
+
+    pub fn after_calls(calls: usize) -> Heartbeat {
+        Heartbeat {
+            interval: None,
+            calls: Some(calls),
+        }
+    }
+}
+
+/// Why a [`HeartbeatDetector`] reported a value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+    First,
+    Changed,
+    /// The value is unchanged but nothing was reported for the [`Heartbeat`] interval or number of calls
+    Heartbeat,
+}
+
+/// Reports unchanged values again after a quiet period, for consumers that treat silence as failure.
+/// Created by [`ChangeDetector::heartbeat`].
+pub struct HeartbeatDetector<T, C = SystemClock, S = DefaultState> where T : ?Sized, S : HashBackend {
+    detector: ChangeDetector<T, S>,
+    policy: Heartbeat,
+    clock: C,
+    reported_at: Option<Instant>,
+    unchanged: usize,
+}
+
+impl <T, S> ChangeDetector<T, S> where T : ?Sized, S : HashBackend {
+    /// Re-reports unchanged values according to `policy`
+    pub fn heartbeat(self, policy: Heartbeat) -> HeartbeatDetector<T, SystemClock, S> {
+        self.heartbeat_with_clock(policy, SystemClock)
+    }
+
+    /// Like [`ChangeDetector::heartbeat`] with a custom clock.
+    /// When the detector already holds a hash, e.g. a restored one, the quiet period starts now.
+    pub fn heartbeat_with_clock<C>(self, policy: Heartbeat, clock: C) -> HeartbeatDetector<T, C, S> where C : Clock {
+        let reported_at = (!self.untouched()).then(|| clock.now());
+        HeartbeatDetector {
+            detector: self,
+            policy,
+            clock,
+            reported_at,
+            unchanged: 0,
+        }
+    }
+}
+
+impl <T, C, S> HeartbeatDetector<T, C, S> where T : ?Sized, C : Clock, S : HashBackend {
+    /// Check if detector has been used
+    pub fn untouched(&self) -> bool {
+        self.detector.untouched()
+    }
+
+    /// Access the inner hash
+    pub fn hash(&self) -> Option<S::Digest> {
+        self.detector.hash()
+    }
+
+    pub fn policy(&self) -> &Heartbeat {
+        &self.policy
+    }
+
+    fn check(&mut self, hash: S::Digest) -> Option<Reason> {
+        let now = self.clock.now();
+        let first = self.detector.untouched();
+        let reason = if self.detector.update(hash) {
+            if first { Reason::First } else { Reason::Changed }
+        }
+        else {
+            self.unchanged += 1;
+            let calls_reached = self.policy.calls.is_some_and(|calls| self.unchanged >= calls);
+            let interval_reached = match (self.policy.interval, self.reported_at) {
+                (Some(interval), Some(reported_at)) => now.duration_since(reported_at) >= interval,
+                _ => false,
+            };
+            if !calls_reached && !interval_reached {
+                return None;
+            }
+            Reason::Heartbeat
+        };
+        self.reported_at = Some(now);
+        self.unchanged = 0;
+        Some(reason)
+    }
+}
+
+impl <T, C, S> HeartbeatDetector<T, C, S> where T : ?Sized + Hash, C : Clock, S : HashBackend {
+    /// Returns Some when the value differs, is the first value or a heartbeat is due
+    pub fn detect<'a, Q>(&mut self, value: &'a Q) -> Option<(&'a Q, Reason)> where T : Borrow<Q>, Q : ?Sized + Hash {
+        let hash = self.detector.backend.digest(value);
+        self.check(hash).map(|reason| (value, reason))
+    }
+
+    /// Useful to avoid cloning with non-copy types like String
+    pub fn detect_owned(&mut self, value: T) -> Option<(T, Reason)> where T : Sized {
+        let hash = self.detector.backend.digest(&value);
+        self.check(hash).map(|reason| (value, reason))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::{ChangeDetector, Heartbeat, ManualClock, Reason, StableState};
+
+    #[test]
+    fn interval_heartbeat_works() {
+        let clock = ManualClock::new();
+        let mut change_detector = ChangeDetector::<str>::new()
+            .heartbeat_with_clock(Heartbeat::every(Duration::from_secs(30)), clock.clone());
+        assert_eq!(change_detector.detect("A"), Some(("A", Reason::First)));
+        clock.advance(Duration::from_secs(20));
+        assert_eq!(change_detector.detect("A"), None);
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(change_detector.detect("A"), Some(("A", Reason::Heartbeat)));
+        assert_eq!(change_detector.detect("A"), None);
+
+        // A change restarts the quiet period
+        clock.advance(Duration::from_secs(20));
+        assert_eq!(change_detector.detect("B"), Some(("B", Reason::Changed)));
+        clock.advance(Duration::from_secs(20));
+        assert_eq!(change_detector.detect("B"), None);
+    }
+
+    #[test]
+    fn call_heartbeat_works() {
+        let policy = Heartbeat { calls: Some(2), ..Heartbeat::every(Duration::from_secs(3600)) };
+        let mut change_detector = ChangeDetector::<u32>::new().heartbeat(policy);
+        assert_eq!(change_detector.detect_owned(1), Some((1, Reason::First)));
+        assert_eq!(change_detector.detect_owned(1), None);
+        assert_eq!(change_detector.detect_owned(1), Some((1, Reason::Heartbeat)));
+        assert_eq!(change_detector.detect_owned(1), None);
+        assert_eq!(change_detector.detect_owned(2), Some((2, Reason::Changed)));
+    }
+
+    #[test]
+    fn restored_detector_gets_heartbeats() {
+        let mut original = ChangeDetector::<u32, StableState>::stable();
+        original.detect(&1);
+        let stable_hash = original.stable_hash().unwrap();
+
+        let clock = ManualClock::new();
+        let restored = ChangeDetector::<u32, StableState>::from_stable_hash(&stable_hash).unwrap();
+        let mut change_detector = restored.heartbeat_with_clock(Heartbeat::every(Duration::from_secs(10)), clock.clone());
+        assert_eq!(change_detector.detect(&1), None);
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(change_detector.detect(&1), Some((&1, Reason::Heartbeat)));
+    }
+}
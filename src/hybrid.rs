@@ -0,0 +1,191 @@
+use std::hash::{BuildHasher, Hash, RandomState};
+use crate::{ChangeDetector, DefaultState, HashBackend};
+
+/// Secondary check of a [`HybridChangeDetector`], only consulted when the primary hashes match
+pub trait Verifier<T> {
+    /// Check if the value matches the recorded one
+    fn matches(&self, value: &T) -> bool;
+
+    /// Record the value for the next check
+    fn record(&mut self, value: &T);
+}
+
+/// Verifies with a second hash computed by an independently seeded hasher
+pub struct SecondHash<S = RandomState> {
+    build_hasher: S,
+    hash: Option<u64>,
+}
+
+impl SecondHash {
+    /// Uses a randomly seeded hasher, which is independent of the primary one
+    pub fn new() -> SecondHash {
+        SecondHash::with_hasher(RandomState::new())
+    }
+}
+
+impl Default for SecondHash {
+    fn default() -> Self {
+        SecondHash::new()
+    }
+}
+
+impl <S> SecondHash<S> where S : BuildHasher {
+    /// `build_hasher` should be seeded differently from the primary hasher
+    pub fn with_hasher(build_hasher: S) -> SecondHash<S> {
+        SecondHash {
+            build_hasher,
+            hash: None,
+        }
+    }
+}
+
+impl <T, S> Verifier<T> for SecondHash<S> where T : Hash, S : BuildHasher {
+    fn matches(&self, value: &T) -> bool {
+        self.hash == Some(self.build_hasher.hash_one(value))
+    }
+
+    fn record(&mut self, value: &T) {
+        self.hash = Some(self.build_hasher.hash_one(value));
+    }
+}
+
+/// Verifies by comparing with a retained copy of the last value, which makes detection exact
+pub struct RetainValue<T> {
+    value: Option<T>,
+}
+
+impl <T> RetainValue<T> {
+    pub fn new() -> RetainValue<T> {
+        RetainValue {
+            value: None,
+        }
+    }
+}
+
+impl <T> Default for RetainValue<T> {
+    fn default() -> Self {
+        RetainValue::new()
+    }
+}
+
+impl <T> Verifier<T> for RetainValue<T> where T : PartialEq + Clone {
+    fn matches(&self, value: &T) -> bool {
+        self.value.as_ref() == Some(value)
+    }
+
+    fn record(&mut self, value: &T) {
+        self.value = Some(value.clone());
+    }
+}
+
+/// Which check decided the outcome of a [`HybridChangeDetector`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecidedBy {
+    /// The primary hash differed or it was the first value
+    Hash,
+    /// The primary hash matched and the verifier made the call
+    Verifier,
+}
+
+/// Compares hashes first and only consults the [`Verifier`] when they match,
+/// so changes hidden by a hash collision are still reported
+pub struct HybridChangeDetector<T, V = SecondHash, S = DefaultState> where S : HashBackend {
+    detector: ChangeDetector<T, S>,
+    verifier: V,
+}
+
+impl <T> HybridChangeDetector<T> where T : Hash {
+    pub fn new() -> HybridChangeDetector<T> {
+        ChangeDetector::new().verified(SecondHash::new())
+    }
+}
+
+impl <T> Default for HybridChangeDetector<T> where T : Hash {
+    fn default() -> Self {
+        HybridChangeDetector::new()
+    }
+}
+
+impl <T, S> ChangeDetector<T, S> where T : Hash, S : HashBackend {
+    /// Confirms matching hashes with `verifier` before reporting a value as unchanged
+    pub fn verified<V>(self, verifier: V) -> HybridChangeDetector<T, V, S> where V : Verifier<T> {
+        HybridChangeDetector {
+            detector: self,
+            verifier,
+        }
+    }
+}
+
+impl <T, V, S> HybridChangeDetector<T, V, S> where T : Hash, V : Verifier<T>, S : HashBackend {
+    /// Check if detector has been used
+    pub fn untouched(&self) -> bool {
+        self.detector.untouched()
+    }
+
+    /// Access the primary hash
+    pub fn hash(&self) -> Option<S::Digest> {
+        self.detector.hash()
+    }
+
+    /// Access the verifier
+    pub fn verifier(&self) -> &V {
+        &self.verifier
+    }
+
+    /// Returns Some when the value differs or is the first value, along with the check that decided
+    pub fn detect<'a>(&mut self, value: &'a T) -> (Option<&'a T>, DecidedBy) {
+        let (change, decided_by) = self.check(value);
+        (change.then_some(value), decided_by)
+    }
+
+    /// Useful to avoid cloning with non-copy types like String
+    pub fn detect_owned(&mut self, value: T) -> (Option<T>, DecidedBy) {
+        let (change, decided_by) = self.check(&value);
+        (change.then_some(value), decided_by)
+    }
+
+    fn check(&mut self, value: &T) -> (bool, DecidedBy) {
+        let hash = self.detector.backend.digest(value);
+        if self.detector.update(hash) {
+            self.verifier.record(value);
+            (true, DecidedBy::Hash)
+        }
+        else if self.verifier.matches(value) {
+            (false, DecidedBy::Verifier)
+        }
+        else {
+            self.verifier.record(value);
+            (true, DecidedBy::Verifier)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ChangeDetector, DecidedBy, HybridChangeDetector, RetainValue, SecondHash};
+    use crate::test_hasher::IdentityState;
+
+    #[test]
+    fn hybrid_change_detect_works() {
+        let mut change_detector = HybridChangeDetector::<usize>::new();
+        assert_eq!(change_detector.detect(&1), (Some(&1), DecidedBy::Hash));
+        assert_eq!(change_detector.detect(&1), (None, DecidedBy::Verifier));
+        assert_eq!(change_detector.detect_owned(2), (Some(2), DecidedBy::Hash));
+    }
+
+    #[test]
+    fn verifier_catches_collisions() {
+        // Every string collides
+        let mut second_hash = ChangeDetector::<&str, _>::with_hasher(IdentityState::default())
+            .verified(SecondHash::new());
+        assert_eq!(second_hash.detect(&"A"), (Some(&"A"), DecidedBy::Hash));
+        assert_eq!(second_hash.detect(&"B"), (Some(&"B"), DecidedBy::Verifier));
+        assert_eq!(second_hash.detect(&"B"), (None, DecidedBy::Verifier));
+
+        let mut retained = ChangeDetector::<&str, _>::with_hasher(IdentityState::default())
+            .verified(RetainValue::new());
+        assert_eq!(retained.detect(&"A"), (Some(&"A"), DecidedBy::Hash));
+        assert_eq!(retained.detect_owned("B"), (Some("B"), DecidedBy::Verifier));
+        assert_eq!(retained.detect_owned("B"), (None, DecidedBy::Verifier));
+    }
+}
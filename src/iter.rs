@@ -0,0 +1,158 @@
+use std::hash::Hash;
+use std::iter::Enumerate;
+use crate::{ByKey, ChangeDetector, DefaultState, HashBackend};
+
+/// Adapters that only yield the items of an iterator that differ from the previous one
+pub trait ChangesExt : Iterator + Sized {
+    /// Skips items that hash the same as the previous item
+    fn changes(self) -> Changes<Self> where Self::Item : Hash {
+        self.changes_from(ChangeDetector::new())
+    }
+
+    /// Like [`ChangesExt::changes`] but continues from the state of `detector`, e.g. one from [`Changes::into_detector`]
+    fn changes_from<S>(self, detector: ChangeDetector<Self::Item, S>) -> Changes<Self, S> where Self::Item : Hash, S : HashBackend {
+        Changes {
+            iter: self,
+            detector,
+        }
+    }
+
+    /// Like [`ChangesExt::changes`] but yields the index of every item in the original iterator as well
+    fn changes_indexed(self) -> ChangesIndexed<Self> where Self::Item : Hash {
+        ChangesIndexed {
+            iter: self.enumerate(),
+            detector: ChangeDetector::new(),
+        }
+    }
+
+    /// Skips items whose key, as returned by `key`, hashes the same as the key of the previous item
+    fn changes_by_key<K, F>(self, key: F) -> ChangesByKey<Self, K, F> where K : Hash, F : Fn(&Self::Item) -> K {
+        ChangesByKey {
+            iter: self,
+            detector: ChangeDetector::by_key(key),
+        }
+    }
+}
+
+impl <I> ChangesExt for I where I : Iterator {}
+
+/// Iterator returned by [`ChangesExt::changes`]
+pub struct Changes<I, S = DefaultState> where I : Iterator, S : HashBackend {
+    iter: I,
+    detector: ChangeDetector<I::Item, S>,
+}
+
+impl <I, S> Changes<I, S> where I : Iterator, I::Item : Hash, S : HashBackend {
+    /// Access the detector, which holds the hash of the last yielded item
+    pub fn detector(&self) -> &ChangeDetector<I::Item, S> {
+        &self.detector
+    }
+
+    /// Stops iterating and returns the detector, to resume with [`ChangesExt::changes_from`] later
+    pub fn into_detector(self) -> ChangeDetector<I::Item, S> {
+        self.detector
+    }
+}
+
+impl <I, S> Iterator for Changes<I, S> where I : Iterator, I::Item : Hash, S : HashBackend {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let detector = &mut self.detector;
+        self.iter.find_map(|item| detector.detect_owned(item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+/// Iterator returned by [`ChangesExt::changes_indexed`]
+pub struct ChangesIndexed<I, S = DefaultState> where I : Iterator, S : HashBackend {
+    iter: Enumerate<I>,
+    detector: ChangeDetector<I::Item, S>,
+}
+
+impl <I, S> ChangesIndexed<I, S> where I : Iterator, I::Item : Hash, S : HashBackend {
+    /// Access the detector, which holds the hash of the last yielded item
+    pub fn detector(&self) -> &ChangeDetector<I::Item, S> {
+        &self.detector
+    }
+
+    /// Stops iterating and returns the detector, to resume with [`ChangesExt::changes_from`] later
+    pub fn into_detector(self) -> ChangeDetector<I::Item, S> {
+        self.detector
+    }
+}
+
+impl <I, S> Iterator for ChangesIndexed<I, S> where I : Iterator, I::Item : Hash, S : HashBackend {
+    type Item = (usize, I::Item);
+
+    fn next(&mut self) -> Option<(usize, I::Item)> {
+        let detector = &mut self.detector;
+        self.iter.find_map(|(index, item)| detector.detect_owned(item).map(|item| (index, item)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+/// Iterator returned by [`ChangesExt::changes_by_key`]
+pub struct ChangesByKey<I, K, F, S = DefaultState> where I : Iterator, S : HashBackend {
+    iter: I,
+    detector: ByKey<I::Item, K, F, S>,
+}
+
+impl <I, K, F, S> ChangesByKey<I, K, F, S> where I : Iterator, K : Hash, F : Fn(&I::Item) -> K, S : HashBackend {
+    /// Access the detector, which holds the hash of the key of the last yielded item
+    pub fn detector(&self) -> &ByKey<I::Item, K, F, S> {
+        &self.detector
+    }
+
+    /// Stops iterating and returns the detector
+    pub fn into_detector(self) -> ByKey<I::Item, K, F, S> {
+        self.detector
+    }
+}
+
+impl <I, K, F, S> Iterator for ChangesByKey<I, K, F, S> where I : Iterator, K : Hash, F : Fn(&I::Item) -> K, S : HashBackend {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let detector = &mut self.detector;
+        self.iter.find_map(|item| detector.detect_owned(item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ChangesExt;
+
+    #[test]
+    fn changes_works() {
+        let changes: Vec<usize> = [1, 1, 2, 2, 2, 1, 3].into_iter().changes().collect();
+        assert_eq!(changes, vec![1, 2, 1, 3]);
+
+        let indexed: Vec<(usize, &str)> = ["a", "a", "b", "b", "a"].into_iter().changes_indexed().collect();
+        assert_eq!(indexed, vec![(0, "a"), (2, "b"), (4, "a")]);
+
+        let by_key: Vec<(u32, &str)> = [(1, "a"), (1, "b"), (2, "c")].into_iter().changes_by_key(|(version, _)| *version).collect();
+        assert_eq!(by_key, vec![(1, "a"), (2, "c")]);
+    }
+
+    #[test]
+    fn scan_can_be_resumed() {
+        let mut changes = vec![1, 1, 2].into_iter().changes();
+        assert_eq!(changes.by_ref().collect::<Vec<_>>(), vec![1, 2]);
+        let detector = changes.into_detector();
+        assert!(!detector.untouched());
+
+        let resumed: Vec<usize> = vec![2, 2, 3].into_iter().changes_from(detector).collect();
+        assert_eq!(resumed, vec![3]);
+    }
+}
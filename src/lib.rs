@@ -1,38 +1,143 @@
-use std::hash::{DefaultHasher, Hash, Hasher};
+use std::borrow::Borrow;
+use std::hash::{BuildHasherDefault, DefaultHasher, Hash};
 use std::marker::PhantomData;
 
-/// Type-safe wrapper around a hash intended to avoid accidental mix-ups
-pub struct ChangeDetector<T> {
-    hash: u64,
+mod atomic;
+mod backend;
+mod change;
+mod clock;
+mod debounce;
+mod detector_map;
+mod heartbeat;
+mod hybrid;
+mod iter;
+mod map;
+mod numeric;
+mod pending;
+mod projection;
+mod sequence;
+#[cfg(feature = "serde")]
+mod serialize;
+mod stability;
+mod stable;
+mod store;
+#[cfg(feature = "async")]
+mod stream;
+mod throttle;
+mod track;
+mod unordered;
+mod value;
+
+pub use atomic::AtomicChangeDetector;
+pub use backend::HashBackend;
+#[cfg(feature = "blake3")]
+pub use backend::Blake3State;
+#[cfg(feature = "sha256")]
+pub use backend::Sha256State;
+pub use change::Change;
+pub use clock::{Clock, ManualClock, SystemClock};
+pub use debounce::DebouncedDetector;
+pub use detector_map::ChangeDetectorMap;
+pub use heartbeat::{Heartbeat, HeartbeatDetector, Reason};
+pub use hybrid::{DecidedBy, HybridChangeDetector, RetainValue, SecondHash, Verifier};
+pub use iter::{Changes, ChangesByKey, ChangesExt, ChangesIndexed};
+pub use map::{MapChangeDetector, MapDiff};
+pub use numeric::{NumericChangeDetector, Threshold, Tolerance};
+pub use pending::{Outcome, PendingChange};
+pub use projection::ByKey;
+pub use sequence::{SequenceChangeDetector, SequenceDiff};
+pub use stability::{Candidate, StableChangeDetector};
+pub use stable::{Portable, PortableHash, Stable128Hasher, Stable128State, StableAlgorithm, StableHash, StableHasher, StableState};
+pub use store::{PersistentDetectorStore, StoredDetector};
+#[cfg(feature = "async")]
+pub use stream::{ChangeReceiver, ChangesStream, StreamChangesExt};
+pub use throttle::ThrottledDetector;
+pub use track::{ChangeTrack, FieldTracker};
+#[cfg(feature = "derive")]
+pub use change_detector_derive::ChangeTrack;
+pub use unordered::{Unordered, UnorderedHash};
+pub use value::ValueChangeDetector;
+
+/// Hasher used by [`ChangeDetector::new`], builds a `DefaultHasher` for every value
+pub type DefaultState = BuildHasherDefault<DefaultHasher>;
+
+/// Type-safe wrapper around a hash intended to avoid accidental mix-ups.
+///
+/// `T` may be unsized, e.g. `ChangeDetector<str>` accepts `&str` directly.
+pub struct ChangeDetector<T, S = DefaultState> where T : ?Sized, S : HashBackend {
+    /// None until the first value has been observed
+    hash: Option<S::Digest>,
+    backend: S,
     phantom: PhantomData<T>,
 }
 
-impl <T> ChangeDetector<T> where T : Hash {
+impl <T> ChangeDetector<T> where T : ?Sized {
     pub fn new() -> ChangeDetector<T> {
+        ChangeDetector::with_hasher(DefaultState::default())
+    }
+}
+
+impl <T> Default for ChangeDetector<T> where T : ?Sized {
+    fn default() -> Self {
+        ChangeDetector::new()
+    }
+}
+
+impl <T> ChangeDetector<T, StableState> where T : ?Sized {
+    /// Uses a fixed hashing algorithm so hashes can be persisted, see [`StableState`]
+    pub fn stable() -> ChangeDetector<T, StableState> {
+        ChangeDetector::with_hasher(StableState)
+    }
+}
+
+impl <T, S> ChangeDetector<T, S> where T : ?Sized, S : HashBackend {
+    /// Uses `backend` to hash every value, e.g. a faster or seeded `BuildHasher` or a wider digest
+    pub fn with_hasher(backend: S) -> ChangeDetector<T, S> {
         ChangeDetector {
-            hash: 0, // About a 1 in 18 quintillion chance of hash collision with initial value
+            hash: None,
+            backend,
             phantom: Default::default(),
         }
     }
 
+    /// Access the hasher used for values
+    pub fn hasher(&self) -> &S {
+        &self.backend
+    }
+
     /// Check if detector has been used
     pub fn untouched(&self) -> bool {
-        self.hash == 0
+        self.hash.is_none()
     }
 
-    /// Access the inner hash
-    pub fn hash(&self) -> u64 {
+    /// Access the inner hash, None when no value has been observed yet
+    pub fn hash(&self) -> Option<S::Digest> {
         self.hash
     }
 
-    /// Returns Some when the value differs or is the first value
-    pub fn detect<'a>(&mut self, value: &'a T) -> Option<&'a T> {
-        let mut hasher = DefaultHasher::new();
-        value.hash(&mut hasher);
-        let hash = hasher.finish();
-        let change = self.hash != hash;
-        self.hash = hash;
-        if change {
+    /// Stores the hash and returns whether it differs from the previous one
+    fn update(&mut self, hash: S::Digest) -> bool {
+        self.hash.replace(hash) != Some(hash)
+    }
+
+    /// Stores the hash and wraps the value in a [`Change`] describing the transition
+    fn update_change<V>(&mut self, hash: S::Digest, value: V) -> Change<V, S::Digest> {
+        match self.hash.replace(hash) {
+            None => Change::First(value),
+            Some(previous_hash) if previous_hash != hash => Change::Changed { value, previous_hash },
+            Some(_) => Change::Unchanged,
+        }
+    }
+}
+
+impl <T, S> ChangeDetector<T, S> where T : ?Sized + Hash, S : HashBackend {
+    /// Returns Some when the value differs or is the first value.
+    ///
+    /// Like `HashMap::get` any borrowed form of `T` is accepted, e.g. `&str` for `ChangeDetector<String>`,
+    /// which hashes the same as the owned form.
+    pub fn detect<'a, Q>(&mut self, value: &'a Q) -> Option<&'a Q> where T : Borrow<Q>, Q : ?Sized + Hash {
+        let hash = self.backend.digest(value);
+        if self.update(hash) {
             Some(value)
         }
         else {
@@ -41,24 +146,79 @@ impl <T> ChangeDetector<T> where T : Hash {
     }
 
     /// Useful to avoid cloning with non-copy types like String
-    pub fn detect_owned(&mut self, value: T) -> Option<T> {
-        let mut hasher = DefaultHasher::new();
-        value.hash(&mut hasher);
-        let hash = hasher.finish();
-        let change = self.hash != hash;
-        self.hash = hash;
-        if change {
+    pub fn detect_owned(&mut self, value: T) -> Option<T> where T : Sized {
+        let hash = self.backend.digest(&value);
+        if self.update(hash) {
             Some(value)
         }
         else {
             None
         }
     }
+
+    /// Like [`ChangeDetector::detect`] but tells the first value apart from a change
+    pub fn detect_change<'a, Q>(&mut self, value: &'a Q) -> Change<&'a Q, S::Digest> where T : Borrow<Q>, Q : ?Sized + Hash {
+        let hash = self.backend.digest(value);
+        self.update_change(hash, value)
+    }
+
+    /// Like [`ChangeDetector::detect_owned`] but tells the first value apart from a change
+    pub fn detect_change_owned(&mut self, value: T) -> Change<T, S::Digest> where T : Sized {
+        let hash = self.backend.digest(&value);
+        self.update_change(hash, value)
+    }
+}
+
+impl <T, S> ChangeDetector<T, S> where T : ?Sized + Hash, S : HashBackend + StableAlgorithm {
+    /// Inner hash tagged with the algorithm that produced it, None when no value has been observed yet
+    pub fn stable_hash(&self) -> Option<StableHash<S::Digest>> {
+        self.hash.map(|hash| StableHash {
+            algorithm: S::ALGORITHM.to_string(),
+            version: S::VERSION,
+            hash,
+        })
+    }
+
+    /// Restores a detector from a persisted hash, returns None when it was produced by another algorithm
+    pub fn from_stable_hash(stored: &StableHash<S::Digest>) -> Option<ChangeDetector<T, S>> where S : Default {
+        if !stored.is_from::<S>() {
+            return None;
+        }
+        let mut change_detector = ChangeDetector::with_hasher(S::default());
+        change_detector.hash = Some(stored.hash);
+        Some(change_detector)
+    }
+}
+
+/// Hasher for tests that need to pick the hash of a value
+#[cfg(test)]
+pub(crate) mod test_hasher {
+    use std::hash::{BuildHasherDefault, Hasher};
+
+    /// Uses the last written `u64` as the hash and ignores other writes, so e.g. every `&str` hashes to 0
+    #[derive(Default)]
+    pub(crate) struct IdentityHasher(u64);
+
+    impl Hasher for IdentityHasher {
+        fn finish(&self) -> u64 {
+            self.0
+        }
+
+        fn write(&mut self, _bytes: &[u8]) {}
+
+        fn write_u64(&mut self, i: u64) {
+            self.0 = i;
+        }
+    }
+
+    pub(crate) type IdentityState = BuildHasherDefault<IdentityHasher>;
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::ChangeDetector;
+    use std::hash::{BuildHasher, RandomState};
+    use crate::{ChangeDetector, StableHash, StableState};
+    use crate::test_hasher::IdentityState;
 
     #[test]
     fn change_detect_works() {
@@ -101,4 +261,74 @@ mod tests {
 
         assert_eq!(writes, 2);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn custom_hasher_works() {
+        let mut change_detector = ChangeDetector::<&str, _>::with_hasher(RandomState::new());
+        assert_eq!(change_detector.detect(&"A"), Some(&"A"));
+        assert_eq!(change_detector.detect(&"A"), None);
+        assert_eq!(change_detector.detect(&"B"), Some(&"B"));
+        assert_eq!(change_detector.hash(), Some(change_detector.hasher().hash_one("B")));
+    }
+
+    #[test]
+    fn stable_hash_round_trip() {
+        let mut change_detector = ChangeDetector::<String, _>::stable();
+        assert!(change_detector.stable_hash().is_none());
+        change_detector.detect_owned("A".to_string());
+        let stored = change_detector.stable_hash().unwrap();
+        assert_eq!(stored.hash, 0x0907c907b59fe649);
+
+        let mut restored = ChangeDetector::<String, StableState>::from_stable_hash(&stored).unwrap();
+        assert_eq!(restored.detect_owned("A".to_string()), None);
+        assert_eq!(restored.detect_owned("B".to_string()), Some("B".to_string()));
+
+        let other = StableHash { version: stored.version + 1, ..stored };
+        assert!(ChangeDetector::<String, StableState>::from_stable_hash(&other).is_none());
+    }
+
+    #[test]
+    fn zero_hash_is_observed() {
+        let mut change_detector = ChangeDetector::<u64, _>::with_hasher(IdentityState::default());
+        assert!(change_detector.untouched());
+        assert_eq!(change_detector.hash(), None);
+        assert_eq!(change_detector.detect(&0), Some(&0));
+        assert!(!change_detector.untouched());
+        assert_eq!(change_detector.hash(), Some(0));
+        assert_eq!(change_detector.detect_owned(0), None);
+        assert!(!change_detector.untouched());
+
+        let mut owned_change_detector = ChangeDetector::<u64, _>::with_hasher(IdentityState::default());
+        assert_eq!(owned_change_detector.detect_owned(0), Some(0));
+        assert!(!owned_change_detector.untouched());
+    }
+
+    #[test]
+    fn borrowed_forms_work() {
+        let mut string_change_detector = ChangeDetector::<String>::new();
+        assert_eq!(string_change_detector.detect("A"), Some("A"));
+        assert_eq!(string_change_detector.detect_owned("A".to_string()), None);
+        assert_eq!(string_change_detector.detect(&"A".to_string()), None);
+        assert_eq!(string_change_detector.detect("B"), Some("B"));
+
+        let mut vec_change_detector = ChangeDetector::<Vec<u8>>::new();
+        assert_eq!(vec_change_detector.detect(&[1_u8, 2][..]), Some(&[1_u8, 2][..]));
+        assert_eq!(vec_change_detector.detect_owned(vec![1, 2]), None);
+    }
+
+    #[test]
+    fn unsized_targets_work() {
+        let mut str_change_detector = ChangeDetector::<str>::new();
+        assert_eq!(str_change_detector.detect("A"), Some("A"));
+        assert_eq!(str_change_detector.detect("A"), None);
+        let owned_hash = ChangeDetector::<String>::new().hasher().hash_one("A".to_string());
+        assert_eq!(str_change_detector.hash(), Some(owned_hash));
+
+        let mut bytes_change_detector = ChangeDetector::<[u8], _>::stable();
+        let input = b"line one\nline two".to_vec();
+        for line in input.split(|byte| *byte == b'\n') {
+            assert_eq!(bytes_change_detector.detect(line), Some(line));
+        }
+        assert_eq!(bytes_change_detector.detect(&b"line two"[..]), None);
+    }
+}
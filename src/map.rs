@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::marker::PhantomData;
+use crate::{DefaultState, HashBackend};
+
+/// Keys that differ between two observations of a map, returned by [`MapChangeDetector::detect`].
+/// The order of the keys is unspecified.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapDiff<K> {
+    pub added: Vec<K>,
+    pub removed: Vec<K>,
+    pub modified: Vec<K>,
+}
+
+impl <K> MapDiff<K> {
+    /// Check if nothing changed, only possible for the first observation of an empty map
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// Keeps a hash per key to report the delta between observations of a `HashMap`, `BTreeMap` or any other map
+pub struct MapChangeDetector<K, V, S = DefaultState> where S : HashBackend {
+    /// None until the first map has been observed
+    hashes: Option<HashMap<K, S::Digest>>,
+    backend: S,
+    phantom: PhantomData<V>,
+}
+
+impl <K, V> MapChangeDetector<K, V> where K : Clone + Eq + Hash, V : Hash {
+    pub fn new() -> MapChangeDetector<K, V> {
+        MapChangeDetector::with_hasher(DefaultState::default())
+    }
+}
+
+impl <K, V> Default for MapChangeDetector<K, V> where K : Clone + Eq + Hash, V : Hash {
+    fn default() -> Self {
+        MapChangeDetector::new()
+    }
+}
+
+impl <K, V, S> MapChangeDetector<K, V, S> where K : Clone + Eq + Hash, V : Hash, S : HashBackend {
+    pub fn with_hasher(backend: S) -> MapChangeDetector<K, V, S> {
+        MapChangeDetector {
+            hashes: None,
+            backend,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Check if detector has been used
+    pub fn untouched(&self) -> bool {
+        self.hashes.is_none()
+    }
+
+    /// Access the hash of the value of a key
+    pub fn hash(&self, key: &K) -> Option<S::Digest> {
+        self.hashes.as_ref()?.get(key).copied()
+    }
+
+    /// Returns Some when any entry was added, removed or modified, or it is the first map, in which case every key counts as added.
+    ///
+    /// Takes the entries by reference, e.g. `&map` or `map.iter()`, so values are never cloned.
+    /// Keys are only cloned when they are new or end up in the diff.
+    pub fn detect<'a, I>(&mut self, entries: I) -> Option<MapDiff<K>> where I : IntoIterator<Item = (&'a K, &'a V)>, K : 'a, V : 'a {
+        let first = self.hashes.is_none();
+        let mut previous = self.hashes.take().unwrap_or_default();
+        let mut hashes = HashMap::with_capacity(previous.len());
+        let mut diff = MapDiff {
+            added: Vec::new(),
+            removed: Vec::new(),
+            modified: Vec::new(),
+        };
+        for (key, value) in entries {
+            let hash = self.backend.digest(value);
+            match previous.remove_entry(key) {
+                Some((key, previous_hash)) => {
+                    if previous_hash != hash {
+                        diff.modified.push(key.clone());
+                    }
+                    hashes.insert(key, hash);
+                }
+                None => {
+                    diff.added.push(key.clone());
+                    hashes.insert(key.clone(), hash);
+                }
+            }
+        }
+        diff.removed.extend(previous.into_keys());
+        self.hashes = Some(hashes);
+        if diff.is_empty() && !first {
+            None
+        }
+        else {
+            Some(diff)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, HashMap};
+    use crate::{MapChangeDetector, MapDiff};
+
+    #[test]
+    fn map_diff_works() {
+        let mut change_detector = MapChangeDetector::<&str, usize>::new();
+        let mut map = BTreeMap::from([("a", 1), ("b", 2)]);
+        assert_eq!(change_detector.detect(&map), Some(MapDiff { added: vec!["a", "b"], removed: vec![], modified: vec![] }));
+        assert_eq!(change_detector.detect(&map), None);
+
+        map.insert("b", 3);
+        map.insert("c", 4);
+        map.remove("a");
+        assert_eq!(change_detector.detect(&map), Some(MapDiff { added: vec!["c"], removed: vec!["a"], modified: vec!["b"] }));
+        assert!(change_detector.hash(&"a").is_none());
+        assert!(change_detector.hash(&"c").is_some());
+    }
+
+    #[test]
+    fn works_with_hash_maps_without_cloning_values() {
+        /// Not `Clone`, so values can't be copied into the detector
+        #[derive(Hash)]
+        struct Setting(String);
+
+        let mut change_detector = MapChangeDetector::<String, Setting>::new();
+        let mut map = HashMap::from([("theme".to_string(), Setting("dark".to_string()))]);
+        assert!(change_detector.detect(&map).is_some());
+        assert!(change_detector.detect(map.iter()).is_none());
+
+        map.insert("theme".to_string(), Setting("light".to_string()));
+        map.insert("font".to_string(), Setting("mono".to_string()));
+        let mut diff = change_detector.detect(&map).unwrap();
+        diff.added.sort();
+        assert_eq!(diff, MapDiff { added: vec!["font".to_string()], removed: vec![], modified: vec!["theme".to_string()] });
+    }
+}
@@ -0,0 +1,187 @@
+/// How far a number may move from the last accepted value before it counts as a change
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Threshold {
+    /// Change when the difference is larger than this epsilon
+    Absolute(f64),
+    /// Change when the difference is larger than this fraction of the last accepted value
+    Relative(f64),
+    /// Change when the value drops more than `below` or rises more than `above`.
+    /// Different bounds give hysteresis, e.g. reacting to rising temperatures sooner than to falling ones.
+    Deadband { below: f64, above: f64 },
+    /// Change when the value rounds to a different multiple of this step
+    Quantized(f64),
+}
+
+impl Threshold {
+    fn exceeded(&self, value: f64, previous: f64) -> bool {
+        if value.is_nan() || previous.is_nan() {
+            return value.is_nan() != previous.is_nan();
+        }
+        match *self {
+            Threshold::Absolute(epsilon) => (value - previous).abs() > epsilon,
+            Threshold::Relative(ratio) => (value - previous).abs() > ratio * previous.abs(),
+            Threshold::Deadband { below, above } => value < previous - below || value > previous + above,
+            Threshold::Quantized(step) => (value / step).round() != (previous / step).round(),
+        }
+    }
+}
+
+/// Decides whether a value moved far enough from the last accepted one.
+///
+/// Implemented for `f32` and `f64`, structs of floats can implement it with a threshold per field.
+pub trait Tolerance {
+    type Threshold;
+
+    fn exceeds(&self, previous: &Self, threshold: &Self::Threshold) -> bool;
+}
+
+impl Tolerance for f64 {
+    type Threshold = Threshold;
+
+    fn exceeds(&self, previous: &f64, threshold: &Threshold) -> bool {
+        threshold.exceeded(*self, *previous)
+    }
+}
+
+impl Tolerance for f32 {
+    type Threshold = Threshold;
+
+    fn exceeds(&self, previous: &f32, threshold: &Threshold) -> bool {
+        threshold.exceeded(*self as f64, *previous as f64)
+    }
+}
+
+/// Keeps the last accepted value and ignores movement within a threshold, for noisy readings like sensors.
+///
+/// Values are compared with the last accepted value rather than the previous one,
+/// so slow drift is still reported once it adds up.
+pub struct NumericChangeDetector<T = f64> where T : Tolerance {
+    value: Option<T>,
+    threshold: T::Threshold,
+}
+
+impl <T> NumericChangeDetector<T> where T : Tolerance + Clone {
+    pub fn new(threshold: T::Threshold) -> NumericChangeDetector<T> {
+        NumericChangeDetector {
+            value: None,
+            threshold,
+        }
+    }
+
+    /// Check if detector has been used
+    pub fn untouched(&self) -> bool {
+        self.value.is_none()
+    }
+
+    /// Access the last accepted value
+    pub fn value(&self) -> Option<&T> {
+        self.value.as_ref()
+    }
+
+    pub fn threshold(&self) -> &T::Threshold {
+        &self.threshold
+    }
+
+    fn accepts(&self, value: &T) -> bool {
+        match &self.value {
+            Some(previous) => value.exceeds(previous, &self.threshold),
+            None => true,
+        }
+    }
+
+    /// Returns Some when the value moved past the threshold or is the first value
+    pub fn detect<'a>(&mut self, value: &'a T) -> Option<&'a T> {
+        if self.accepts(value) {
+            self.value = Some(value.clone());
+            Some(value)
+        }
+        else {
+            None
+        }
+    }
+
+    /// Returns Some when the value moved past the threshold or is the first value
+    pub fn detect_owned(&mut self, value: T) -> Option<T> {
+        if self.accepts(&value) {
+            self.value = Some(value.clone());
+            Some(value)
+        }
+        else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{NumericChangeDetector, Threshold, Tolerance};
+
+    #[test]
+    fn thresholds_work() {
+        let mut absolute = NumericChangeDetector::new(Threshold::Absolute(0.5));
+        assert_eq!(absolute.detect_owned(20.0), Some(20.0));
+        assert_eq!(absolute.detect_owned(20.3), None);
+        assert_eq!(absolute.detect_owned(20.4), None);
+        // Drift adds up against the last accepted value
+        assert_eq!(absolute.detect_owned(20.6), Some(20.6));
+        assert_eq!(absolute.value(), Some(&20.6));
+
+        let mut relative = NumericChangeDetector::<f32>::new(Threshold::Relative(0.1));
+        assert_eq!(relative.detect_owned(100.0), Some(100.0));
+        assert_eq!(relative.detect_owned(109.0), None);
+        assert_eq!(relative.detect_owned(111.0), Some(111.0));
+
+        let mut deadband = NumericChangeDetector::new(Threshold::Deadband { below: 2.0, above: 0.5 });
+        assert_eq!(deadband.detect_owned(20.0), Some(20.0));
+        assert_eq!(deadband.detect_owned(18.5), None);
+        assert_eq!(deadband.detect_owned(20.6), Some(20.6));
+        assert_eq!(deadband.detect_owned(18.5), Some(18.5));
+
+        let mut quantized = NumericChangeDetector::new(Threshold::Quantized(10.0));
+        assert_eq!(quantized.detect_owned(12.0), Some(12.0));
+        assert_eq!(quantized.detect_owned(14.9), None);
+        assert_eq!(quantized.detect_owned(15.0), Some(15.0));
+    }
+
+    #[test]
+    fn nan_only_changes_once() {
+        let mut change_detector = NumericChangeDetector::new(Threshold::Absolute(0.5));
+        assert_eq!(change_detector.detect_owned(1.0), Some(1.0));
+        assert!(change_detector.detect_owned(f64::NAN).is_some());
+        assert!(change_detector.detect_owned(f64::NAN).is_none());
+        assert_eq!(change_detector.detect_owned(1.0), Some(1.0));
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Reading {
+        temperature: f64,
+        humidity: f32,
+    }
+
+    struct ReadingThreshold {
+        temperature: Threshold,
+        humidity: Threshold,
+    }
+
+    impl Tolerance for Reading {
+        type Threshold = ReadingThreshold;
+
+        fn exceeds(&self, previous: &Reading, threshold: &ReadingThreshold) -> bool {
+            self.temperature.exceeds(&previous.temperature, &threshold.temperature)
+                || self.humidity.exceeds(&previous.humidity, &threshold.humidity)
+        }
+    }
+
+    #[test]
+    fn per_field_tolerance_works() {
+        let mut change_detector = NumericChangeDetector::new(ReadingThreshold {
+            temperature: Threshold::Absolute(0.5),
+            humidity: Threshold::Relative(0.05),
+        });
+        let first = Reading { temperature: 20.0, humidity: 40.0 };
+        assert_eq!(change_detector.detect(&first), Some(&first));
+        assert_eq!(change_detector.detect_owned(Reading { temperature: 20.4, humidity: 41.0 }), None);
+        let humid = Reading { temperature: 20.4, humidity: 43.0 };
+        assert_eq!(change_detector.detect_owned(humid.clone()), Some(humid));
+    }
+}
@@ -0,0 +1,130 @@
+use std::borrow::Borrow;
+use std::hash::Hash;
+use crate::{ChangeDetector, HashBackend};
+
+/// Hash of a value that hasn't been stored yet, see [`ChangeDetector::prepare`].
+///
+/// Dropping it without calling [`PendingChange::commit`] leaves the detector untouched,
+/// so a failed write can be retried with the same value.
+#[must_use = "the detector is only updated when the pending change is committed"]
+pub struct PendingChange<'d, T, S> where T : ?Sized, S : HashBackend {
+    detector: &'d mut ChangeDetector<T, S>,
+    hash: S::Digest,
+}
+
+/// Result of [`ChangeDetector::detect_and_then`] when the closure succeeded or wasn't called
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome<R> {
+    /// The value changed and the closure returned this
+    Changed(R),
+    /// The value didn't change so the closure wasn't called
+    Unchanged,
+}
+
+impl <R> Outcome<R> {
+    /// Returns Some when the closure was called
+    pub fn changed(self) -> Option<R> {
+        match self {
+            Outcome::Changed(result) => Some(result),
+            Outcome::Unchanged => None,
+        }
+    }
+}
+
+impl <T, S> ChangeDetector<T, S> where T : ?Sized + Hash, S : HashBackend {
+    /// Check if the value differs or is the first value, without storing it
+    pub fn would_change<Q>(&self, value: &Q) -> bool where T : Borrow<Q>, Q : ?Sized + Hash {
+        self.hash != Some(self.backend.digest(value))
+    }
+
+    /// Hashes the value without storing it, call [`PendingChange::commit`] once it has been handled
+    pub fn prepare<Q>(&mut self, value: &Q) -> PendingChange<'_, T, S> where T : Borrow<Q>, Q : ?Sized + Hash {
+        let hash = self.backend.digest(value);
+        PendingChange {
+            detector: self,
+            hash,
+        }
+    }
+
+    /// Calls `f` when the value differs or is the first value, only storing the hash when it returns Ok.
+    /// Useful for writing data somewhere, a failed write is retried on the next call.
+    pub fn detect_and_then<R, E, F>(&mut self, value: T, f: F) -> Result<Outcome<R>, E> where T : Sized, F : FnOnce(T) -> Result<R, E> {
+        let pending = self.prepare(&value);
+        if !pending.is_change() {
+            return Ok(Outcome::Unchanged);
+        }
+        let result = f(value)?;
+        pending.commit();
+        Ok(Outcome::Changed(result))
+    }
+}
+
+impl <T, S> PendingChange<'_, T, S> where T : ?Sized + Hash, S : HashBackend {
+    /// Check if the value differs or is the first value
+    pub fn is_change(&self) -> bool {
+        self.detector.hash != Some(self.hash)
+    }
+
+    /// Access the hash that will be stored
+    pub fn hash(&self) -> S::Digest {
+        self.hash
+    }
+
+    /// Stores the hash in the detector, returns whether it was a change
+    pub fn commit(self) -> bool {
+        self.detector.update(self.hash)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ChangeDetector, Outcome};
+
+    #[test]
+    fn would_change_does_not_store() {
+        let mut change_detector = ChangeDetector::<usize>::new();
+        assert!(change_detector.would_change(&1));
+        assert!(change_detector.untouched());
+        assert_eq!(change_detector.detect(&1), Some(&1));
+        assert!(!change_detector.would_change(&1));
+        assert!(change_detector.would_change(&2));
+        assert_eq!(change_detector.detect(&2), Some(&2));
+    }
+
+    #[test]
+    fn pending_change_only_stored_on_commit() {
+        let mut change_detector = ChangeDetector::<String>::new();
+        let value = "A".to_string();
+
+        let pending = change_detector.prepare(&value);
+        assert!(pending.is_change());
+        drop(pending); // e.g. the write failed
+        assert!(change_detector.untouched());
+
+        let pending = change_detector.prepare(&value);
+        assert!(pending.is_change());
+        assert!(pending.commit());
+        assert!(!change_detector.would_change(&value));
+        assert!(!change_detector.prepare(&value).commit());
+    }
+
+    #[test]
+    fn detect_and_then_rolls_back_on_error() {
+        let mut change_detector = ChangeDetector::<Vec<usize>>::new();
+        let mut written = Vec::new();
+
+        let failed: Result<Outcome<()>, &str> = change_detector.detect_and_then(vec![1, 2, 3], |_nums| Err("disk full"));
+        assert_eq!(failed, Err("disk full"));
+        assert!(change_detector.untouched());
+
+        let mut write = |change_detector: &mut ChangeDetector<Vec<usize>>, nums: Vec<usize>| {
+            change_detector.detect_and_then(nums, |nums| -> Result<usize, &str> {
+                written.push(nums);
+                Ok(written.len())
+            })
+        };
+        assert_eq!(write(&mut change_detector, vec![1, 2, 3]), Ok(Outcome::Changed(1)));
+        assert_eq!(write(&mut change_detector, vec![1, 2, 3]), Ok(Outcome::Unchanged));
+        assert_eq!(write(&mut change_detector, vec![1, 2, 3, 4]).map(Outcome::changed), Ok(Some(2)));
+    }
+}
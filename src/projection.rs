@@ -0,0 +1,85 @@
+use std::hash::Hash;
+use std::marker::PhantomData;
+use crate::{ChangeDetector, DefaultState, HashBackend};
+
+/// Detects changes to a key derived from the value, created by [`ChangeDetector::by_key`]
+pub struct ByKey<T, K, F, S = DefaultState> where T : ?Sized, S : HashBackend {
+    detector: ChangeDetector<K, S>,
+    key: F,
+    phantom: PhantomData<fn(&T)>,
+}
+
+impl <T> ChangeDetector<T> where T : ?Sized {
+    /// Only hashes the key returned by `key`, e.g. a version field, but still hands back the full value on change
+    pub fn by_key<K, F>(key: F) -> ByKey<T, K, F> where K : Hash, F : Fn(&T) -> K {
+        ChangeDetector::by_key_with_hasher(key, DefaultState::default())
+    }
+}
+
+impl <T, S> ChangeDetector<T, S> where T : ?Sized, S : HashBackend {
+    /// Like [`ChangeDetector::by_key`] with a custom hasher
+    pub fn by_key_with_hasher<K, F>(key: F, backend: S) -> ByKey<T, K, F, S> where K : Hash, F : Fn(&T) -> K {
+        ByKey {
+            detector: ChangeDetector::with_hasher(backend),
+            key,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl <T, K, F, S> ByKey<T, K, F, S> where T : ?Sized, K : Hash, F : Fn(&T) -> K, S : HashBackend {
+    /// Check if detector has been used
+    pub fn untouched(&self) -> bool {
+        self.detector.untouched()
+    }
+
+    /// Access the hash of the last key
+    pub fn hash(&self) -> Option<S::Digest> {
+        self.detector.hash()
+    }
+
+    /// Returns Some when the key of the value differs or it is the first value
+    pub fn detect<'a>(&mut self, value: &'a T) -> Option<&'a T> {
+        let key = (self.key)(value);
+        self.detector.detect(&key).map(|_| value)
+    }
+
+    /// Useful to avoid cloning with non-copy types like String
+    pub fn detect_owned(&mut self, value: T) -> Option<T> where T : Sized {
+        let key = (self.key)(&value);
+        self.detector.detect(&key).map(|_| value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ChangeDetector;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Document {
+        version: u32,
+        title: String,
+        body: Vec<u8>,
+    }
+
+    #[test]
+    fn by_key_works() {
+        let mut change_detector = ChangeDetector::<Document>::by_key(|document| document.version);
+        let document = Document { version: 1, title: "A".to_string(), body: vec![1, 2, 3] };
+        assert!(change_detector.untouched());
+        assert_eq!(change_detector.detect(&document), Some(&document));
+
+        let edited = Document { title: "B".to_string(), ..document.clone() };
+        assert_eq!(change_detector.detect_owned(edited.clone()), None);
+        let published = Document { version: 2, ..edited };
+        assert_eq!(change_detector.detect_owned(published.clone()), Some(published));
+    }
+
+    #[test]
+    fn by_key_on_unsized_values_works() {
+        let mut change_detector = ChangeDetector::<str>::by_key(|line| line.split(',').next().map(str::to_string));
+        assert_eq!(change_detector.detect("a,1"), Some("a,1"));
+        assert_eq!(change_detector.detect("a,2"), None);
+        assert_eq!(change_detector.detect("b,2"), Some("b,2"));
+    }
+}
@@ -0,0 +1,108 @@
+use std::hash::Hash;
+use std::marker::PhantomData;
+use std::ops::Range;
+use crate::{DefaultState, HashBackend};
+
+/// Which elements of a sequence changed, returned by [`SequenceChangeDetector::detect`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SequenceDiff {
+    /// Indices present in both sequences whose element differs
+    pub changed: Vec<usize>,
+    /// Indices of elements added at the end
+    pub appended: Range<usize>,
+    /// Indices of the previous sequence that were cut off at the end
+    pub truncated: Range<usize>,
+}
+
+impl SequenceDiff {
+    /// Check if nothing changed, only possible for the first observation of an empty sequence
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.appended.is_empty() && self.truncated.is_empty()
+    }
+}
+
+/// Keeps a hash per element to report which indices changed instead of just whether the whole sequence did
+pub struct SequenceChangeDetector<T, S = DefaultState> where S : HashBackend {
+    /// None until the first sequence has been observed
+    hashes: Option<Vec<S::Digest>>,
+    backend: S,
+    phantom: PhantomData<T>,
+}
+
+impl <T> SequenceChangeDetector<T> where T : Hash {
+    pub fn new() -> SequenceChangeDetector<T> {
+        SequenceChangeDetector::with_hasher(DefaultState::default())
+    }
+}
+
+impl <T> Default for SequenceChangeDetector<T> where T : Hash {
+    fn default() -> Self {
+        SequenceChangeDetector::new()
+    }
+}
+
+impl <T, S> SequenceChangeDetector<T, S> where T : Hash, S : HashBackend {
+    pub fn with_hasher(backend: S) -> SequenceChangeDetector<T, S> {
+        SequenceChangeDetector {
+            hashes: None,
+            backend,
+            phantom: PhantomData,
+        }
+    }
+
+    /// Check if detector has been used
+    pub fn untouched(&self) -> bool {
+        self.hashes.is_none()
+    }
+
+    /// Access the hashes of the elements, None when no sequence has been observed yet
+    pub fn hashes(&self) -> Option<&[S::Digest]> {
+        self.hashes.as_deref()
+    }
+
+    /// Returns Some when any element differs or it is the first sequence, in which case every element counts as appended
+    pub fn detect(&mut self, values: &[T]) -> Option<SequenceDiff> {
+        let hashes: Vec<S::Digest> = values.iter().map(|value| self.backend.digest(value)).collect();
+        let previous = self.hashes.replace(hashes);
+        let first = previous.is_none();
+        let previous = previous.unwrap_or_default();
+        let current = self.hashes.as_deref().unwrap_or_default();
+
+        let common = previous.len().min(current.len());
+        let diff = SequenceDiff {
+            changed: (0..common).filter(|index| previous[*index] != current[*index]).collect(),
+            appended: common..current.len(),
+            truncated: common..previous.len(),
+        };
+        if diff.is_empty() && !first {
+            None
+        }
+        else {
+            Some(diff)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{SequenceChangeDetector, SequenceDiff};
+
+    #[test]
+    fn sequence_diff_works() {
+        let mut change_detector = SequenceChangeDetector::<usize>::new();
+        assert_eq!(change_detector.detect(&[1, 2, 3]), Some(SequenceDiff { changed: vec![], appended: 0..3, truncated: 0..0 }));
+        assert_eq!(change_detector.detect(&[1, 2, 3]), None);
+        assert_eq!(change_detector.detect(&[1, 5, 3, 4]), Some(SequenceDiff { changed: vec![1], appended: 3..4, truncated: 3..3 }));
+        assert_eq!(change_detector.detect(&[0, 5]), Some(SequenceDiff { changed: vec![0], appended: 2..2, truncated: 2..4 }));
+        assert_eq!(change_detector.hashes().map(<[u64]>::len), Some(2));
+    }
+
+    #[test]
+    fn first_empty_sequence_is_reported() {
+        let mut change_detector = SequenceChangeDetector::<String>::new();
+        assert!(change_detector.untouched());
+        assert!(change_detector.detect(&[]).is_some_and(|diff| diff.is_empty()));
+        assert!(!change_detector.untouched());
+        assert_eq!(change_detector.detect(&[]), None);
+    }
+}
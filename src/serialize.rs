@@ -0,0 +1,78 @@
+use std::borrow::Cow;
+use std::marker::PhantomData;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::Error;
+use crate::{ChangeDetector, HashBackend, StableAlgorithm};
+
+/// Serialized form of a detector, a `None` hash means no value has been observed yet
+#[derive(Serialize, Deserialize)]
+#[serde(rename = "ChangeDetector")]
+struct State<'a, D> {
+    #[serde(borrow)]
+    algorithm: Cow<'a, str>,
+    version: u32,
+    hash: Option<D>,
+}
+
+/// Only implemented for stable backends, a `DefaultHasher` hash is meaningless after a restart
+impl <T, S> Serialize for ChangeDetector<T, S> where T : ?Sized, S : HashBackend + StableAlgorithm, S::Digest : Serialize {
+    fn serialize<Z>(&self, serializer: Z) -> Result<Z::Ok, Z::Error> where Z : Serializer {
+        State {
+            algorithm: Cow::Borrowed(S::ALGORITHM),
+            version: S::VERSION,
+            hash: self.hash,
+        }.serialize(serializer)
+    }
+}
+
+/// Fails when the state was written by another algorithm or version
+impl <'de, T, S> Deserialize<'de> for ChangeDetector<T, S> where T : ?Sized, S : HashBackend + StableAlgorithm + Default, S::Digest : Deserialize<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D : Deserializer<'de> {
+        let state = State::<S::Digest>::deserialize(deserializer)?;
+        if state.algorithm != S::ALGORITHM || state.version != S::VERSION {
+            return Err(D::Error::custom(format!(
+                "hash was written by {} version {}, expected {} version {}",
+                state.algorithm, state.version, S::ALGORITHM, S::VERSION,
+            )));
+        }
+        Ok(ChangeDetector {
+            hash: state.hash,
+            backend: S::default(),
+            phantom: PhantomData,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ChangeDetector, Stable128State, StableState};
+
+    #[test]
+    fn round_trip_works() {
+        let untouched = ChangeDetector::<String, _>::stable();
+        let json = serde_json::to_string(&untouched).unwrap();
+        assert_eq!(json, r#"{"algorithm":"fnv1a-64-le","version":1,"hash":null}"#);
+        let mut restored: ChangeDetector<String, StableState> = serde_json::from_str(&json).unwrap();
+        assert!(restored.untouched());
+
+        assert_eq!(restored.detect("A"), Some("A"));
+        let json = serde_json::to_string(&restored).unwrap();
+        let mut restored: ChangeDetector<String, StableState> = serde_json::from_str(&json).unwrap();
+        assert!(!restored.untouched());
+        assert_eq!(restored.detect("A"), None);
+        assert_eq!(restored.detect("B"), Some("B"));
+    }
+
+    #[test]
+    fn other_algorithms_are_refused() {
+        let mut change_detector = ChangeDetector::<str, _>::with_hasher(Stable128State);
+        change_detector.detect("A");
+        let json = serde_json::to_string(&change_detector).unwrap();
+        assert!(serde_json::from_str::<ChangeDetector<str, StableState>>(&json).is_err());
+        assert!(serde_json::from_str::<ChangeDetector<str, Stable128State>>(&json).is_ok());
+
+        let newer = r#"{"algorithm":"fnv1a-64-le","version":2,"hash":1}"#;
+        let error = serde_json::from_str::<ChangeDetector<str, StableState>>(newer).err().unwrap();
+        assert!(error.to_string().contains("expected fnv1a-64-le version 1"));
+    }
+}
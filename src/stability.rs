@@ -0,0 +1,136 @@
+use std::borrow::Borrow;
+use std::hash::Hash;
+use crate::{ChangeDetector, DefaultState, HashBackend};
+
+/// New value that hasn't been seen enough times in a row yet, see [`StableChangeDetector::pending`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Candidate<D = u64> {
+    pub hash: D,
+    /// Number of consecutive times the value has been seen
+    pub count: usize,
+}
+
+/// Only reports a change once the same new value has been seen a number of times in a row,
+/// so a single odd reading from e.g. a health check doesn't flip the state.
+///
+/// The first value has to be seen that many times as well. Not to be confused with the stable hashes of [`crate::StableState`].
+pub struct StableChangeDetector<T, S = DefaultState> where T : ?Sized, S : HashBackend {
+    detector: ChangeDetector<T, S>,
+    required: usize,
+    candidate: Option<Candidate<S::Digest>>,
+}
+
+impl <T> StableChangeDetector<T> where T : ?Sized {
+    /// Requiring 1 sample behaves like a plain [`ChangeDetector`]
+    pub fn new(required: usize) -> StableChangeDetector<T> {
+        StableChangeDetector::with_hasher(required, DefaultState::default())
+    }
+}
+
+impl <T, S> StableChangeDetector<T, S> where T : ?Sized, S : HashBackend {
+    pub fn with_hasher(required: usize, backend: S) -> StableChangeDetector<T, S> {
+        StableChangeDetector {
+            detector: ChangeDetector::with_hasher(backend),
+            required,
+            candidate: None,
+        }
+    }
+
+    /// Check if a value has been reported
+    pub fn untouched(&self) -> bool {
+        self.detector.untouched()
+    }
+
+    /// Access the detector, which holds the hash of the last reported value
+    pub fn detector(&self) -> &ChangeDetector<T, S> {
+        &self.detector
+    }
+
+    /// Number of consecutive samples needed before a change is reported
+    pub fn required(&self) -> usize {
+        self.required
+    }
+
+    /// Access the value that is transitioning, None when the last sample matched the reported value
+    pub fn pending(&self) -> Option<Candidate<S::Digest>> {
+        self.candidate
+    }
+
+    fn settle(&mut self, hash: S::Digest) -> bool {
+        if self.detector.hash == Some(hash) {
+            self.candidate = None;
+            return false;
+        }
+        let count = match self.candidate {
+            Some(candidate) if candidate.hash == hash => candidate.count + 1,
+            _ => 1,
+        };
+        if count >= self.required {
+            self.candidate = None;
+            self.detector.update(hash)
+        }
+        else {
+            self.candidate = Some(Candidate { hash, count });
+            false
+        }
+    }
+}
+
+impl <T, S> StableChangeDetector<T, S> where T : ?Sized + Hash, S : HashBackend {
+    /// Returns Some when this is the required consecutive sample of a new value
+    pub fn detect<'a, Q>(&mut self, value: &'a Q) -> Option<&'a Q> where T : Borrow<Q>, Q : ?Sized + Hash {
+        let hash = self.detector.backend.digest(value);
+        if self.settle(hash) {
+            Some(value)
+        }
+        else {
+            None
+        }
+    }
+
+    /// Useful to avoid cloning with non-copy types like String
+    pub fn detect_owned(&mut self, value: T) -> Option<T> where T : Sized {
+        let hash = self.detector.backend.digest(&value);
+        if self.settle(hash) {
+            Some(value)
+        }
+        else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::StableChangeDetector;
+
+    #[test]
+    fn stability_window_works() {
+        let mut change_detector = StableChangeDetector::<str>::new(3);
+        assert_eq!(change_detector.detect("up"), None);
+        assert_eq!(change_detector.detect("up"), None);
+        assert_eq!(change_detector.pending().map(|candidate| candidate.count), Some(2));
+        assert_eq!(change_detector.detect("up"), Some("up"));
+        assert!(change_detector.pending().is_none());
+
+        // A single odd reading doesn't flip the state
+        assert_eq!(change_detector.detect("down"), None);
+        assert_eq!(change_detector.detect("up"), None);
+        assert!(change_detector.pending().is_none());
+
+        // A different reading restarts the count
+        assert_eq!(change_detector.detect("down"), None);
+        assert_eq!(change_detector.detect("degraded"), None);
+        assert_eq!(change_detector.detect("down"), None);
+        assert_eq!(change_detector.detect("down"), None);
+        assert_eq!(change_detector.detect("down"), Some("down"));
+    }
+
+    #[test]
+    fn single_sample_behaves_like_change_detector() {
+        let mut change_detector = StableChangeDetector::<u32>::new(1);
+        assert_eq!(change_detector.detect_owned(1), Some(1));
+        assert_eq!(change_detector.detect_owned(1), None);
+        assert_eq!(change_detector.detect_owned(2), Some(2));
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::VecDeque;
+use std::hash::{BuildHasher, Hash, Hasher};
+use crate::HashBackend;
+
+/// Implemented by hashers with a fixed, documented algorithm whose output survives Rust upgrades
+pub trait StableAlgorithm {
+    /// Identifier stored next to persisted hashes
+    const ALGORITHM: &'static str;
+    /// Bumped whenever the output for the same input changes
+    const VERSION: u32;
+}
+
+/// A hash tagged with the algorithm that produced it, intended to be persisted
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StableHash<D = u64> {
+    pub algorithm: String,
+    pub version: u32,
+    pub hash: D,
+}
+
+impl <D> StableHash<D> {
+    /// Check if the hash was produced by the current version of `A`
+    pub fn is_from<A: StableAlgorithm>(&self) -> bool {
+        self.algorithm == A::ALGORITHM && self.version == A::VERSION
+    }
+}
+
+/// Writes integers as little-endian bytes and `usize`/`isize` as 64 bits,
+/// so single integers don't depend on the platform.
+/// Slices of integers skip these writes and are written as raw memory, see [`Portable`].
+macro_rules! stable_integer_writes {
+    ($($method:ident: $ty:ty => $as:ty),* $(,)?) => {
+        $(
+            fn $method(&mut self, i: $ty) {
+                self.write(&(i as $as).to_le_bytes());
+            }
+        )*
+    };
+}
+
+#[cfg(any(feature = "sha256", feature = "blake3"))]
+pub(crate) use stable_integer_writes;
+
+/// FNV-1a over little-endian input, see [`StableState`]
+#[derive(Debug, Clone)]
+pub struct StableHasher {
+    state: u64,
+}
+
+const FNV_OFFSET_BASIS_64: u64 = 0xcbf29ce484222325;
+const FNV_PRIME_64: u64 = 0x100000001b3;
+const FNV_OFFSET_BASIS_128: u128 = 0x6c62272e07bb014262b821756295c58d;
+const FNV_PRIME_128: u128 = 0x1000000000000000000013b;
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        StableHasher { state: FNV_OFFSET_BASIS_64 }
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.state
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.state ^= *byte as u64;
+            self.state = self.state.wrapping_mul(FNV_PRIME_64);
+        }
+    }
+
+    stable_integer_writes! {
+        write_u16: u16 => u16,
+        write_u32: u32 => u32,
+        write_u64: u64 => u64,
+        write_u128: u128 => u128,
+        write_usize: usize => u64,
+        write_i16: i16 => i16,
+        write_i32: i32 => i32,
+        write_i64: i64 => i64,
+        write_i128: i128 => i128,
+        write_isize: isize => i64,
+    }
+}
+
+/// Hashes with 64-bit FNV-1a, feeding integers as little-endian bytes.
+///
+/// Unlike `DefaultHasher` the algorithm is fixed, so hashes can be stored between runs.
+/// The hash still depends on the `Hash` implementation of the value itself.
+/// `[T]::hash` writes slices of integers, like `Vec<usize>` or `Vec<u32>`, as their raw memory,
+/// so wrap them in [`Portable`] when hashes are shared between 32/64-bit or big/little-endian platforms.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StableState;
+
+impl BuildHasher for StableState {
+    type Hasher = StableHasher;
+
+    fn build_hasher(&self) -> StableHasher {
+        StableHasher::default()
+    }
+}
+
+impl StableAlgorithm for StableState {
+    const ALGORITHM: &'static str = "fnv1a-64-le";
+    const VERSION: u32 = 1;
+}
+
+/// 128-bit FNV-1a over little-endian input, see [`Stable128State`]
+#[derive(Debug, Clone)]
+pub struct Stable128Hasher {
+    state: u128,
+}
+
+impl Stable128Hasher {
+    /// Full 128-bit hash, [`Hasher::finish`] only returns the lower half
+    pub fn finish128(&self) -> u128 {
+        self.state
+    }
+}
+
+impl Default for Stable128Hasher {
+    fn default() -> Self {
+        Stable128Hasher { state: FNV_OFFSET_BASIS_128 }
+    }
+}
+
+impl Hasher for Stable128Hasher {
+    fn finish(&self) -> u64 {
+        self.state as u64
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.state ^= *byte as u128;
+            self.state = self.state.wrapping_mul(FNV_PRIME_128);
+        }
+    }
+
+    stable_integer_writes! {
+        write_u16: u16 => u16,
+        write_u32: u32 => u32,
+        write_u64: u64 => u64,
+        write_u128: u128 => u128,
+        write_usize: usize => u64,
+        write_i16: i16 => i16,
+        write_i32: i32 => i32,
+        write_i64: i64 => i64,
+        write_i128: i128 => i128,
+        write_isize: isize => i64,
+    }
+}
+
+/// Hashes with 128-bit FNV-1a, the same input rules as [`StableState`] with a `u128` digest
+/// to make collisions unlikely across millions of distinct values
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Stable128State;
+
+impl HashBackend for Stable128State {
+    type Digest = u128;
+
+    fn digest<V>(&self, value: &V) -> u128 where V : Hash + ?Sized {
+        let mut hasher = Stable128Hasher::default();
+        value.hash(&mut hasher);
+        hasher.finish128()
+    }
+}
+
+impl StableAlgorithm for Stable128State {
+    const ALGORITHM: &'static str = "fnv1a-128-le";
+    const VERSION: u32 = 1;
+}
+
+/// Hashing that writes the elements of a sequence one at a time, see [`Portable`]
+pub trait PortableHash {
+    fn hash_portable<H>(&self, state: &mut H) where H : Hasher;
+}
+
+fn hash_elements<'a, I, T, H>(elements: I, state: &mut H) where I : ExactSizeIterator<Item = &'a T>, T : 'a + Hash, H : Hasher {
+    state.write_usize(elements.len());
+    for element in elements {
+        element.hash(state);
+    }
+}
+
+impl <T> PortableHash for [T] where T : Hash {
+    fn hash_portable<H>(&self, state: &mut H) where H : Hasher {
+        hash_elements(self.iter(), state);
+    }
+}
+
+impl <T, const N: usize> PortableHash for [T; N] where T : Hash {
+    fn hash_portable<H>(&self, state: &mut H) where H : Hasher {
+        hash_elements(self.iter(), state);
+    }
+}
+
+impl <T> PortableHash for Vec<T> where T : Hash {
+    fn hash_portable<H>(&self, state: &mut H) where H : Hasher {
+        hash_elements(self.iter(), state);
+    }
+}
+
+impl <T> PortableHash for VecDeque<T> where T : Hash {
+    fn hash_portable<H>(&self, state: &mut H) where H : Hasher {
+        hash_elements(self.iter(), state);
+    }
+}
+
+impl <C> PortableHash for &C where C : ?Sized + PortableHash {
+    fn hash_portable<H>(&self, state: &mut H) where H : Hasher {
+        (**self).hash_portable(state);
+    }
+}
+
+/// Implements `Hash` through [`PortableHash`], e.g. `ChangeDetector<Portable<Vec<usize>>, StableState>`.
+///
+/// The elements go through the stable integer writes instead of being written as raw memory,
+/// so the hash is the same on 32/64-bit and big/little-endian platforms.
+/// Only the outer sequence is affected, nested sequences need their own `Portable`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Portable<C>(pub C);
+
+impl <C> Hash for Portable<C> where C : PortableHash {
+    fn hash<H>(&self, state: &mut H) where H : Hasher {
+        self.0.hash_portable(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::hash::{BuildHasher, Hasher};
+    use crate::Portable;
+    use crate::stable::{Stable128Hasher, StableHasher, StableState};
+
+    #[test]
+    fn matches_fnv1a_test_vectors() {
+        let mut hasher = StableHasher::default();
+        assert_eq!(hasher.finish(), 0xcbf29ce484222325);
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xaf63dc4c8601ec8c);
+        let mut hasher = StableHasher::default();
+        hasher.write(b"foobar");
+        assert_eq!(hasher.finish(), 0x85944171f73967e8);
+
+        let mut hasher = Stable128Hasher::default();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish128(), 0xd228cb696f1a8caf78912b704e4a8964);
+    }
+
+    #[test]
+    fn integers_are_little_endian() {
+        let mut bytes = StableHasher::default();
+        bytes.write(&[1, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(StableState.hash_one(1_u64), bytes.finish());
+        assert_eq!(StableState.hash_one(1_usize), bytes.finish());
+        assert_eq!(StableState.hash_one("A"), 0x0907c907b59fe649);
+    }
+
+    #[test]
+    fn portable_writes_elements_one_at_a_time() {
+        let mut usizes = StableHasher::default();
+        usizes.write(&[2, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 2, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(StableState.hash_one(Portable(vec![1_usize, 2])), usizes.finish());
+        assert_eq!(StableState.hash_one(Portable([1_usize, 2].as_slice())), usizes.finish());
+
+        let mut u32s = StableHasher::default();
+        u32s.write(&[2, 0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0]);
+        assert_eq!(StableState.hash_one(Portable(vec![1_u32, 2])), u32s.finish());
+        assert_eq!(StableState.hash_one(Portable([1_u32, 2])), u32s.finish());
+    }
+}
@@ -0,0 +1,246 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File, OpenOptions};
+use std::hash::Hash;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use crate::{ChangeDetector, StableHash, StableState};
+
+const HEADER: &str = "change-detector-store 1";
+
+/// Detector states of several jobs kept in a single file, e.g. to only notify when a nightly run differs from the last one.
+///
+/// The store holds an exclusive lock on `<path>.lock` until dropped, so concurrent runs wait for each other.
+/// Every save writes `<path>.tmp` and renames it over the file, so a crash never leaves a partial file behind.
+pub struct PersistentDetectorStore {
+    path: PathBuf,
+    entries: BTreeMap<String, StableHash>,
+    _lock: File,
+}
+
+impl PersistentDetectorStore {
+    /// Locks and loads the store, blocking while another process holds it. A missing file is an empty store.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<PersistentDetectorStore> {
+        let path = path.as_ref().to_path_buf();
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(sibling(&path, "lock"))?;
+        lock.lock()?;
+        let entries = match File::open(&path) {
+            Ok(file) => read_entries(BufReader::new(file))?,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(error) => return Err(error),
+        };
+        Ok(PersistentDetectorStore {
+            path,
+            entries,
+            _lock: lock,
+        })
+    }
+
+    /// Detector restored from the state stored under `key`, untouched when there is none
+    /// or it was written by another hashing algorithm. Call [`StoredDetector::commit`] to save it.
+    pub fn detector<T>(&mut self, key: &str) -> StoredDetector<'_, T> where T : ?Sized + Hash {
+        let detector = self.entries.get(key)
+            .and_then(ChangeDetector::from_stable_hash)
+            .unwrap_or_else(ChangeDetector::stable);
+        StoredDetector {
+            store: self,
+            key: key.to_string(),
+            detector,
+        }
+    }
+
+    /// Check if a state is stored under `key`
+    pub fn contains(&self, key: &str) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Keys with a stored state, in sorted order
+    pub fn keys(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    /// Removes the state stored under `key` and saves, returns whether there was one
+    pub fn remove(&mut self, key: &str) -> io::Result<bool> {
+        if self.entries.remove(key).is_none() {
+            return Ok(false);
+        }
+        self.save()?;
+        Ok(true)
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let temporary = sibling(&self.path, "tmp");
+        let mut writer = BufWriter::new(File::create(&temporary)?);
+        writeln!(writer, "{HEADER}")?;
+        for (key, stored) in &self.entries {
+            writeln!(writer, "{}\t{}\t{}\t{:016x}", escape(key), stored.algorithm, stored.version, stored.hash)?;
+        }
+        writer.into_inner().map_err(io::IntoInnerError::into_error)?.sync_all()?;
+        fs::rename(&temporary, &self.path)
+    }
+}
+
+/// A detector borrowed from a [`PersistentDetectorStore`], derefs to [`ChangeDetector`]
+pub struct StoredDetector<'s, T> where T : ?Sized {
+    store: &'s mut PersistentDetectorStore,
+    key: String,
+    detector: ChangeDetector<T, StableState>,
+}
+
+impl <T> StoredDetector<'_, T> where T : ?Sized + Hash {
+    /// Writes the detector state back and saves the store.
+    /// Dropping without committing keeps the previously stored state.
+    pub fn commit(self) -> io::Result<()> {
+        match self.detector.stable_hash() {
+            Some(stored) => self.store.entries.insert(self.key, stored),
+            None => self.store.entries.remove(&self.key),
+        };
+        self.store.save()
+    }
+}
+
+impl <T> Deref for StoredDetector<'_, T> where T : ?Sized {
+    type Target = ChangeDetector<T, StableState>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.detector
+    }
+}
+
+impl <T> DerefMut for StoredDetector<'_, T> where T : ?Sized {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.detector
+    }
+}
+
+fn sibling(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(".");
+    name.push(extension);
+    PathBuf::from(name)
+}
+
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_entries(reader: impl BufRead) -> io::Result<BTreeMap<String, StableHash>> {
+    let mut lines = reader.lines();
+    match lines.next().transpose()? {
+        Some(header) if header == HEADER => {}
+        Some(header) => return Err(invalid(format!("unknown store header {header:?}"))),
+        None => return Ok(BTreeMap::new()),
+    }
+    let mut entries = BTreeMap::new();
+    for line in lines {
+        let line = line?;
+        let fields: Vec<&str> = line.split('\t').collect();
+        let [key, algorithm, version, hash] = fields[..] else {
+            return Err(invalid(format!("malformed store entry {line:?}")));
+        };
+        let stored = StableHash {
+            algorithm: algorithm.to_string(),
+            version: version.parse().map_err(|_| invalid(format!("malformed version in {line:?}")))?,
+            hash: u64::from_str_radix(hash, 16).map_err(|_| invalid(format!("malformed hash in {line:?}")))?,
+        };
+        entries.insert(unescape(key)?, stored);
+    }
+    Ok(entries)
+}
+
+/// Keys may contain anything, so tabs, newlines and backslashes are escaped to keep one entry per line
+fn escape(key: &str) -> String {
+    let mut escaped = String::with_capacity(key.len());
+    for char in key.chars() {
+        match char {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            char => escaped.push(char),
+        }
+    }
+    escaped
+}
+
+fn unescape(key: &str) -> io::Result<String> {
+    let mut unescaped = String::with_capacity(key.len());
+    let mut chars = key.chars();
+    while let Some(char) = chars.next() {
+        if char != '\\' {
+            unescaped.push(char);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => unescaped.push('\\'),
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            _ => return Err(invalid(format!("malformed escape in key {key:?}"))),
+        }
+    }
+    Ok(unescaped)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use crate::PersistentDetectorStore;
+    use crate::store::{escape, unescape};
+
+    fn store_path(name: &str) -> PathBuf {
+        let directory = std::env::temp_dir().join(format!("change-detector-{}-{name}", std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        directory.join("detectors")
+    }
+
+    #[test]
+    fn state_survives_reopening() {
+        let path = store_path("reopen");
+        {
+            let mut store = PersistentDetectorStore::open(&path).unwrap();
+            let mut detector = store.detector::<String>("nightly report");
+            assert!(detector.untouched());
+            assert_eq!(detector.detect("A"), Some("A"));
+            detector.commit().unwrap();
+
+            let mut uncommitted = store.detector::<String>("nightly report");
+            assert_eq!(uncommitted.detect("B"), Some("B"));
+        }
+        {
+            let mut store = PersistentDetectorStore::open(&path).unwrap();
+            assert_eq!(store.keys().collect::<Vec<_>>(), vec!["nightly report"]);
+            let mut detector = store.detector::<str>("nightly report");
+            assert_eq!(detector.detect("A"), None);
+            assert_eq!(detector.detect("B"), Some("B"));
+            detector.commit().unwrap();
+            assert!(store.remove("nightly report").unwrap());
+            assert!(!store.remove("nightly report").unwrap());
+        }
+        let store = PersistentDetectorStore::open(&path).unwrap();
+        assert!(!store.contains("nightly report"));
+        assert!(!path.with_extension("tmp").exists());
+    }
+
+    #[test]
+    fn malformed_files_are_rejected() {
+        let path = store_path("malformed");
+        fs::write(&path, "something else\n").unwrap();
+        assert!(PersistentDetectorStore::open(&path).is_err());
+        fs::write(&path, "change-detector-store 1\nkey\tfnv1a-64-le\n").unwrap();
+        assert!(PersistentDetectorStore::open(&path).is_err());
+    }
+
+    #[test]
+    fn keys_are_escaped() {
+        let key = "tab\there\nnew line \\ backslash";
+        assert!(!escape(key).contains(['\t', '\n']));
+        assert_eq!(unescape(&escape(key)).unwrap(), key);
+    }
+}
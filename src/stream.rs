@@ -0,0 +1,185 @@
+use std::hash::Hash;
+use std::pin::Pin;
+use std::task::{ready, Context, Poll};
+use futures_core::Stream;
+use pin_project_lite::pin_project;
+use tokio::sync::watch;
+use crate::{ChangeDetector, DefaultState, HashBackend};
+
+/// Stream adapter that only yields the items that differ from the previous one
+pub trait StreamChangesExt : Stream + Sized {
+    /// Skips items that hash the same as the previous item
+    fn changes(self) -> ChangesStream<Self> where Self::Item : Hash {
+        self.changes_from(ChangeDetector::new())
+    }
+
+    /// Like [`StreamChangesExt::changes`] but continues from the state of `detector`
+    fn changes_from<S>(self, detector: ChangeDetector<Self::Item, S>) -> ChangesStream<Self, S> where Self::Item : Hash, S : HashBackend {
+        ChangesStream {
+            stream: self,
+            detector,
+        }
+    }
+}
+
+impl <St> StreamChangesExt for St where St : Stream {}
+
+pin_project! {
+    /// Stream returned by [`StreamChangesExt::changes`]
+    pub struct ChangesStream<St, S = DefaultState> where St : Stream, S : HashBackend {
+        #[pin]
+        stream: St,
+        detector: ChangeDetector<St::Item, S>,
+    }
+}
+
+impl <St, S> ChangesStream<St, S> where St : Stream, St::Item : Hash, S : HashBackend {
+    /// Access the detector, which holds the hash of the last yielded item
+    pub fn detector(&self) -> &ChangeDetector<St::Item, S> {
+        &self.detector
+    }
+
+    /// Stops the stream and returns the detector, to resume with [`StreamChangesExt::changes_from`] later
+    pub fn into_detector(self) -> ChangeDetector<St::Item, S> {
+        self.detector
+    }
+}
+
+impl <St, S> Stream for ChangesStream<St, S> where St : Stream, St::Item : Hash, S : HashBackend {
+    type Item = St::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<St::Item>> {
+        let mut this = self.project();
+        loop {
+            match ready!(this.stream.as_mut().poll_next(cx)) {
+                Some(item) => {
+                    if let Some(item) = this.detector.detect_owned(item) {
+                        return Poll::Ready(Some(item));
+                    }
+                }
+                None => return Poll::Ready(None),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.stream.size_hint().1)
+    }
+}
+
+/// Wraps a `watch::Receiver` so [`ChangeReceiver::changed`] only wakes when the hashed value actually differs,
+/// instead of on every send
+pub struct ChangeReceiver<T, S = DefaultState> where S : HashBackend {
+    receiver: watch::Receiver<T>,
+    detector: ChangeDetector<T, S>,
+}
+
+impl <T> ChangeReceiver<T> where T : Hash {
+    /// The current value of the receiver counts as seen, like with `watch::Receiver`
+    pub fn new(receiver: watch::Receiver<T>) -> ChangeReceiver<T> {
+        ChangeReceiver::with_detector(receiver, ChangeDetector::new())
+    }
+}
+
+impl <T, S> ChangeReceiver<T, S> where T : Hash, S : HashBackend {
+    /// Like [`ChangeReceiver::new`] but continues from the state of `detector`,
+    /// when the current value differs from it the first [`ChangeReceiver::changed`] returns immediately
+    pub fn with_detector(mut receiver: watch::Receiver<T>, mut detector: ChangeDetector<T, S>) -> ChangeReceiver<T, S> {
+        let missed = {
+            let current = receiver.borrow_and_update();
+            if detector.untouched() {
+                detector.detect(&*current);
+                false
+            }
+            else {
+                detector.would_change(&*current)
+            }
+        };
+        if missed {
+            receiver.mark_changed();
+        }
+        ChangeReceiver {
+            receiver,
+            detector,
+        }
+    }
+
+    /// Waits until a value that differs from the last seen one is sent, errors when the sender is dropped
+    pub async fn changed(&mut self) -> Result<(), watch::error::RecvError> {
+        loop {
+            self.receiver.changed().await?;
+            if self.detector.detect(&*self.receiver.borrow_and_update()).is_some() {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Access the latest value, see `watch::Receiver::borrow`
+    pub fn borrow(&self) -> watch::Ref<'_, T> {
+        self.receiver.borrow()
+    }
+
+    /// Access the detector, which holds the hash of the last seen value
+    pub fn detector(&self) -> &ChangeDetector<T, S> {
+        &self.detector
+    }
+
+    pub fn into_inner(self) -> watch::Receiver<T> {
+        self.receiver
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::{stream, FutureExt, StreamExt};
+    use tokio::sync::watch;
+    use crate::{ChangeDetector, ChangeReceiver, StreamChangesExt};
+
+    #[tokio::test]
+    async fn stream_changes_works() {
+        let changes: Vec<usize> = StreamChangesExt::changes(stream::iter([1, 1, 2, 2, 1])).collect().await;
+        assert_eq!(changes, vec![1, 2, 1]);
+
+        let mut changes = StreamChangesExt::changes(stream::iter(["a", "a", "b"]));
+        assert_eq!(changes.next().await, Some("a"));
+        assert_eq!(changes.next().await, Some("b"));
+        assert_eq!(changes.next().await, None);
+        assert!(!changes.into_detector().untouched());
+    }
+
+    #[tokio::test]
+    async fn receiver_only_wakes_on_changes() {
+        let (sender, receiver) = watch::channel("initial".to_string());
+        let mut receiver = ChangeReceiver::new(receiver);
+
+        sender.send("initial".to_string()).unwrap();
+        assert!(receiver.changed().now_or_never().is_none());
+
+        sender.send("updated".to_string()).unwrap();
+        receiver.changed().await.unwrap();
+        assert_eq!(*receiver.borrow(), "updated");
+
+        sender.send("updated".to_string()).unwrap();
+        assert!(receiver.changed().now_or_never().is_none());
+
+        drop(sender);
+        assert!(receiver.changed().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn resumed_receiver_reports_missed_change() {
+        let mut detector = ChangeDetector::new();
+        detector.detect("checkpoint");
+        let (_sender, receiver) = watch::channel("updated");
+        let mut receiver = ChangeReceiver::with_detector(receiver, detector);
+        assert!(matches!(receiver.changed().now_or_never(), Some(Ok(()))));
+        assert_eq!(*receiver.borrow(), "updated");
+        assert!(receiver.changed().now_or_never().is_none());
+
+        let mut detector = ChangeDetector::new();
+        detector.detect("current");
+        let (_sender, receiver) = watch::channel("current");
+        let mut receiver = ChangeReceiver::with_detector(receiver, detector);
+        assert!(receiver.changed().now_or_never().is_none());
+    }
+}
@@ -0,0 +1,137 @@
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+use crate::{ChangeDetector, Clock, DefaultState, HashBackend, SystemClock};
+
+/// Reports changes at most once per interval, changes within the interval are held back and only the latest is kept.
+///
+/// A held back value is reported by the next call after the interval, call [`ThrottledDetector::poll`]
+/// to pick it up when no new values arrive.
+pub struct ThrottledDetector<T, C = SystemClock, S = DefaultState> where S : HashBackend {
+    detector: ChangeDetector<T, S>,
+    clock: C,
+    interval: Duration,
+    reported_at: Option<Instant>,
+    pending: Option<T>,
+}
+
+impl <T> ThrottledDetector<T> {
+    pub fn new(interval: Duration) -> ThrottledDetector<T> {
+        ThrottledDetector::with_clock(interval, SystemClock)
+    }
+}
+
+impl <T, C> ThrottledDetector<T, C> where C : Clock {
+    pub fn with_clock(interval: Duration, clock: C) -> ThrottledDetector<T, C> {
+        ThrottledDetector::with_clock_and_hasher(interval, clock, DefaultState::default())
+    }
+}
+
+impl <T, C, S> ThrottledDetector<T, C, S> where C : Clock, S : HashBackend {
+    pub fn with_clock_and_hasher(interval: Duration, clock: C, backend: S) -> ThrottledDetector<T, C, S> {
+        ThrottledDetector {
+            detector: ChangeDetector::with_hasher(backend),
+            clock,
+            interval,
+            reported_at: None,
+            pending: None,
+        }
+    }
+
+    /// Check if a value has been reported
+    pub fn untouched(&self) -> bool {
+        self.detector.untouched()
+    }
+
+    /// Access the detector, which holds the hash of the last reported value
+    pub fn detector(&self) -> &ChangeDetector<T, S> {
+        &self.detector
+    }
+
+    /// Access the value that is held back until the interval has passed
+    pub fn pending(&self) -> Option<&T> {
+        self.pending.as_ref()
+    }
+
+    /// When the held back value can be reported, None when nothing is held back
+    pub fn deadline(&self) -> Option<Instant> {
+        self.pending.as_ref().and(self.reported_at).map(|reported_at| reported_at + self.interval)
+    }
+
+    fn ready(&self, now: Instant) -> bool {
+        self.reported_at.is_none_or(|reported_at| now.duration_since(reported_at) >= self.interval)
+    }
+}
+
+impl <T, C, S> ThrottledDetector<T, C, S> where T : Hash, C : Clock, S : HashBackend {
+    /// Returns Some when the value differs from the last reported value and the interval has passed,
+    /// otherwise a change is held back
+    pub fn detect_owned(&mut self, value: T) -> Option<T> {
+        let now = self.clock.now();
+        let ready = self.ready(now);
+        let pending = self.detector.prepare(&value);
+        if !pending.is_change() {
+            self.pending = None;
+            return None;
+        }
+        if ready {
+            pending.commit();
+            self.pending = None;
+            self.reported_at = Some(now);
+            Some(value)
+        }
+        else {
+            self.pending = Some(value);
+            None
+        }
+    }
+
+    /// Returns the held back value once the interval has passed
+    pub fn poll(&mut self) -> Option<T> {
+        let now = self.clock.now();
+        if self.pending.is_none() || !self.ready(now) {
+            return None;
+        }
+        let value = self.pending.take()?;
+        self.detector.prepare(&value).commit();
+        self.reported_at = Some(now);
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+    use crate::{Clock, ManualClock, ThrottledDetector};
+
+    #[test]
+    fn throttle_works() {
+        let clock = ManualClock::new();
+        let mut change_detector = ThrottledDetector::with_clock(Duration::from_secs(10), clock.clone());
+        assert_eq!(change_detector.detect_owned(1), Some(1));
+        assert_eq!(change_detector.detect_owned(1), None);
+        assert_eq!(change_detector.detect_owned(2), None);
+        assert_eq!(change_detector.detect_owned(3), None);
+        assert_eq!(change_detector.pending(), Some(&3));
+        assert_eq!(change_detector.poll(), None);
+
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(change_detector.deadline(), Some(clock.now()));
+        assert_eq!(change_detector.poll(), Some(3));
+        assert_eq!(change_detector.poll(), None);
+
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(change_detector.detect_owned(4), Some(4));
+    }
+
+    #[test]
+    fn returning_to_reported_value_drops_pending() {
+        let clock = ManualClock::new();
+        let mut change_detector = ThrottledDetector::with_clock(Duration::from_secs(10), clock.clone());
+        assert_eq!(change_detector.detect_owned("A"), Some("A"));
+        assert_eq!(change_detector.detect_owned("B"), None);
+        assert_eq!(change_detector.detect_owned("A"), None);
+        clock.advance(Duration::from_secs(10));
+        assert_eq!(change_detector.poll(), None);
+        assert_eq!(change_detector.detect_owned("B"), Some("B"));
+    }
+}
@@ -0,0 +1,21 @@
+/// Field-level change tracking, usually implemented with `#[derive(ChangeTrack)]` from the `derive` feature.
+///
+/// The derive generates a `<Name>Tracker` struct with a detector per field and supports these field attributes:
+/// - `#[change(ignore)]` skips the field, e.g. timestamps or caches
+/// - `#[change(key)]` only tracks the fields marked as key, like [`crate::ChangeDetector::by_key`]
+/// - `#[change(tolerance = ...)]` compares the field with a [`crate::NumericChangeDetector`] using the given threshold
+pub trait ChangeTrack {
+    type Tracker: FieldTracker<Self>;
+
+    /// Tracker that hasn't observed a value yet
+    fn tracker() -> Self::Tracker;
+}
+
+/// Reports which fields of a value changed, see [`ChangeTrack`]
+pub trait FieldTracker<T> where T : ?Sized {
+    /// Returns the names of the fields that differ, or of every tracked field for the first value
+    fn detect(&mut self, value: &T) -> Option<Vec<&'static str>>;
+
+    /// Check if tracker has been used
+    fn untouched(&self) -> bool;
+}
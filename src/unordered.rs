@@ -0,0 +1,131 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use crate::{ChangeDetector, HashBackend, Portable, StableState};
+
+/// Hashing that doesn't depend on iteration order, for collections like `HashMap` and `HashSet` that don't implement `Hash`.
+///
+/// Every element is hashed on its own and the digests are combined independently of their order,
+/// so equal collections hash the same regardless of insertion order, capacity or their own hasher.
+pub trait UnorderedHash {
+    /// Digest of every element with `backend`, in iteration order
+    fn element_digests<S>(&self, backend: &S) -> Vec<S::Digest> where S : HashBackend;
+}
+
+/// Spreads the bits of an element hash before summing, so similar elements don't cancel out
+fn mix(mut hash: u64) -> u64 {
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xff51afd7ed558ccd);
+    hash ^= hash >> 33;
+    hash = hash.wrapping_mul(0xc4ceb9fe1a85ec53);
+    hash ^ (hash >> 33)
+}
+
+impl <K, V, H> UnorderedHash for HashMap<K, V, H> where K : Hash, V : Hash {
+    fn element_digests<S>(&self, backend: &S) -> Vec<S::Digest> where S : HashBackend {
+        self.iter().map(|element| backend.digest(&element)).collect()
+    }
+}
+
+impl <T, H> UnorderedHash for HashSet<T, H> where T : Hash {
+    fn element_digests<S>(&self, backend: &S) -> Vec<S::Digest> where S : HashBackend {
+        self.iter().map(|element| backend.digest(element)).collect()
+    }
+}
+
+impl <C> UnorderedHash for &C where C : ?Sized + UnorderedHash {
+    fn element_digests<S>(&self, backend: &S) -> Vec<S::Digest> where S : HashBackend {
+        (**self).element_digests(backend)
+    }
+}
+
+/// Implements `Hash` through [`UnorderedHash`], e.g. to put a `HashSet` inside a struct that derives `Hash`.
+///
+/// A `Hasher` can't be used per element, so elements are hashed with [`StableState`] and their
+/// 64-bit hashes are summed, which caps the collision resistance at 64 bits whatever the detector's backend is.
+/// Prefer [`ChangeDetector::detect_unordered`] with wide backends like [`crate::Stable128State`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Unordered<C>(pub C);
+
+impl <C> Hash for Unordered<C> where C : UnorderedHash {
+    fn hash<H>(&self, state: &mut H) where H : Hasher {
+        let digests = self.0.element_digests(&StableState);
+        state.write_usize(digests.len());
+        state.write_u64(digests.into_iter().fold(0_u64, |sum, digest| sum.wrapping_add(mix(digest))));
+    }
+}
+
+impl <T, S> ChangeDetector<T, S> where T : ?Sized + UnorderedHash, S : HashBackend {
+    /// Hashes every element with the backend and then the sorted digests, so the full digest width is kept
+    fn digest_unordered(&self, value: &T) -> S::Digest {
+        let mut digests = value.element_digests(&self.backend);
+        digests.sort_unstable();
+        self.backend.digest(&Portable(digests))
+    }
+
+    /// Like [`ChangeDetector::detect`] for collections without a `Hash` implementation, like `HashMap`
+    pub fn detect_unordered<'a>(&mut self, value: &'a T) -> Option<&'a T> {
+        let hash = self.digest_unordered(value);
+        if self.update(hash) {
+            Some(value)
+        }
+        else {
+            None
+        }
+    }
+
+    /// Useful to avoid cloning with non-copy types like HashMap
+    pub fn detect_unordered_owned(&mut self, value: T) -> Option<T> where T : Sized {
+        let hash = self.digest_unordered(&value);
+        if self.update(hash) {
+            Some(value)
+        }
+        else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+    use std::hash::{BuildHasher, RandomState};
+    use crate::{ChangeDetector, HashBackend, Portable, Stable128State, StableState, Unordered, UnorderedHash};
+
+    #[test]
+    fn order_and_capacity_are_ignored() {
+        let forward: HashMap<usize, &str> = (0..100).map(|key| (key, "value")).collect();
+        let mut backward = HashMap::with_capacity_and_hasher(1000, RandomState::new());
+        for key in (0..100_usize).rev() {
+            backward.insert(key, "value");
+        }
+        assert_eq!(StableState.hash_one(Unordered(&forward)), StableState.hash_one(Unordered(&backward)));
+
+        backward.insert(0, "other");
+        assert_ne!(StableState.hash_one(Unordered(&forward)), StableState.hash_one(Unordered(&backward)));
+    }
+
+    #[test]
+    fn detect_unordered_works() {
+        let mut change_detector = ChangeDetector::<HashSet<&str>>::new();
+        assert!(change_detector.detect_unordered(&HashSet::from(["a", "b"])).is_some());
+        assert!(change_detector.detect_unordered(&HashSet::from(["b", "a"])).is_none());
+        assert!(change_detector.detect_unordered_owned(HashSet::from(["a"])).is_some());
+
+        let mut wrapped_change_detector = ChangeDetector::<Unordered<HashMap<&str, usize>>>::new();
+        let config = Unordered(HashMap::from([("retries", 3), ("timeout", 30)]));
+        assert!(wrapped_change_detector.detect(&config).is_some());
+        assert!(wrapped_change_detector.detect_owned(config).is_none());
+    }
+
+    #[test]
+    fn detect_unordered_keeps_backend_width() {
+        let set = HashSet::from(["a", "b"]);
+        let mut change_detector = ChangeDetector::<HashSet<&str>, _>::with_hasher(Stable128State);
+        assert!(change_detector.detect_unordered(&set).is_some());
+
+        let mut digests = vec![Stable128State.digest("a"), Stable128State.digest("b")];
+        digests.sort_unstable();
+        assert_eq!(set.element_digests(&Stable128State).len(), 2);
+        assert_eq!(change_detector.hash(), Some(Stable128State.digest(&Portable(digests))));
+    }
+}
@@ -0,0 +1,76 @@
+/// Keeps the last value and compares with `==`, so unlike [`crate::ChangeDetector`] it can't miss a change through a hash collision
+pub struct ValueChangeDetector<T> {
+    value: Option<T>,
+}
+
+impl <T> ValueChangeDetector<T> where T : PartialEq + Clone {
+    pub fn new() -> ValueChangeDetector<T> {
+        ValueChangeDetector {
+            value: None,
+        }
+    }
+
+    /// Check if detector has been used
+    pub fn untouched(&self) -> bool {
+        self.value.is_none()
+    }
+
+    /// Access the last observed value
+    pub fn value(&self) -> Option<&T> {
+        self.value.as_ref()
+    }
+
+    /// Returns Some when the value differs or is the first value
+    pub fn detect<'a>(&mut self, value: &'a T) -> Option<&'a T> {
+        if self.value.as_ref() == Some(value) {
+            None
+        }
+        else {
+            self.value = Some(value.clone());
+            Some(value)
+        }
+    }
+
+    /// Returns Some when the value differs or is the first value
+    pub fn detect_owned(&mut self, value: T) -> Option<T> {
+        if self.value.as_ref() == Some(&value) {
+            None
+        }
+        else {
+            self.value = Some(value.clone());
+            Some(value)
+        }
+    }
+}
+
+impl <T> Default for ValueChangeDetector<T> where T : PartialEq + Clone {
+    fn default() -> Self {
+        ValueChangeDetector::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ValueChangeDetector;
+
+    #[test]
+    fn value_change_detect_works() {
+        let mut change_detector = ValueChangeDetector::<usize>::new();
+        assert!(change_detector.untouched());
+        assert_eq!(change_detector.detect(&1), Some(&1));
+        assert_eq!(change_detector.detect(&1), None);
+        assert_eq!(change_detector.detect(&2), Some(&2));
+        assert_eq!(change_detector.value(), Some(&2));
+        assert!(!change_detector.untouched());
+    }
+
+    #[test]
+    fn owned_value_change_detect_works() {
+        let mut change_detector = ValueChangeDetector::<f64>::new();
+        assert_eq!(change_detector.detect_owned(0.5), Some(0.5));
+        assert_eq!(change_detector.detect_owned(0.5), None);
+        assert_eq!(change_detector.detect_owned(f64::NAN).map(f64::is_nan), Some(true));
+        // NaN never equals itself, so it's reported every time
+        assert_eq!(change_detector.detect_owned(f64::NAN).map(f64::is_nan), Some(true));
+    }
+}